@@ -0,0 +1,356 @@
+use std::{io::Write, path::Path, process::ExitCode, str::FromStr};
+
+use clap::{Parser, Subcommand};
+use rnpm_core::{
+    config::Config,
+    error::{AppError, AppResult},
+    manager::{
+        active_version, current, install, list_local, list_remote, resolve_local, resolve_remote,
+        select_version, set_current, uninstall, Progress, VersionSource, VERSION_ENV,
+    },
+    node::{binary_path, current_bin},
+    shell::{self, env_paths, Shell},
+    shim::{install_shims, shims_dir},
+};
+use tokio_util::sync::CancellationToken;
+
+/// Manage the node versions of rnpm without its window, sharing its settings and installs.
+#[derive(Parser)]
+#[command(name = "rnpm", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Download and install a version, or the newest one matching a range like `18`,
+    /// `>=16 <20` or an alias like `latest`, `lts/*`, `lts/hydrogen`, `lts/-1`
+    Install { version: String },
+    /// Remove an installed version
+    Uninstall { version: String },
+    /// Make an installed version the current one, ranges and aliases pick the newest match
+    Use { version: String },
+    /// List the installed versions
+    Ls,
+    /// List the versions available for download
+    LsRemote {
+        /// Only LTS releases
+        #[arg(long)]
+        lts: bool,
+        /// Ask the mirrors even when the cached list is fresh
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Print the version that applies here: RNPM_NODE_VERSION, the project's, or the current one
+    Current,
+    /// Put node, npm, npx and corepack shims running the version each folder asks for in
+    /// node_dir/shims, rnpm-shim is expected next to rnpm
+    Shims,
+    /// Print shell code putting the shims and the bin folder of the version that applies here
+    /// first on PATH, what the shell integration runs whenever the folder changes
+    Env {
+        /// bash, zsh, fish or powershell, the login shell by default
+        #[arg(long)]
+        shell: Option<String>,
+        /// Also set RNPM_NODE_VERSION to an installed version or range, switching only the
+        /// shell that evaluates it: `eval "$(rnpm env --use 18)"`
+        #[arg(long = "use", value_name = "VERSION", conflicts_with = "reset")]
+        version: Option<String>,
+        /// Unset RNPM_NODE_VERSION again
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Add the integration running `rnpm env` to the startup files of shells, or take it out
+    Setup {
+        /// bash, zsh, fish or powershell, the login shell by default
+        #[arg(long)]
+        shell: Vec<String>,
+        /// Take the integration out again
+        #[arg(long)]
+        remove: bool,
+        /// Only show which files would change and how
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the path of the node binary of an installed version or range, the one that applies
+    /// here by default
+    Which { version: Option<String> },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rnpm: {}", e.message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> AppResult<()> {
+    let config = Config::load()?;
+
+    match command {
+        Command::Install { version } => {
+            let client = config.http_client()?;
+            let version = resolve_remote(&config, &client, &version, &Stderr).await?;
+            if list_local(&config)?.contains(&version) {
+                println!("node {version} is already installed");
+                return Ok(());
+            }
+            install(
+                &config,
+                &client,
+                &version,
+                &Stderr,
+                &CancellationToken::new(),
+            )
+            .await?;
+            println!("installed node {version}");
+            Ok(())
+        }
+        Command::Uninstall { version } => {
+            let version = normalize(&version);
+            uninstall(&config, &version).await?;
+            if current(&config)?.as_deref() == Some(version.as_str()) {
+                eprintln!(
+                    "rnpm: {version} was the current version, pick another one with `rnpm use`"
+                );
+            }
+            println!("uninstalled node {version}");
+            Ok(())
+        }
+        Command::Use { version } => {
+            let version = resolve_local(&config, &version)?;
+            set_current(&config, &version).await?;
+            println!("now using node {version}");
+            Ok(())
+        }
+        Command::Ls => {
+            let current = current(&config)?;
+            for version in list_local(&config)? {
+                let marker = if Some(&version) == current.as_ref() {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{marker} {version}");
+            }
+            Ok(())
+        }
+        Command::LsRemote { lts, refresh } => {
+            let client = config.http_client()?;
+            let list = list_remote(&config, &client, refresh, &Stderr).await?;
+            if let Some(error) = list.error {
+                eprintln!("rnpm: showing the cached list, the mirrors are unreachable: {error}");
+            }
+            if let Some(error) = list.cache_error {
+                eprintln!("rnpm: {error}");
+            }
+
+            let installed = list_local(&config)?;
+            for node in list.nodes.iter().filter(|node| !lts || node.lts.is_some()) {
+                let mut line = node.version.clone();
+                if let Some(lts) = &node.lts {
+                    line.push_str(&format!(" ({lts})"));
+                }
+                if installed.contains(&node.version) {
+                    line.push_str(" installed");
+                } else if !node.available {
+                    line.push_str(" unavailable");
+                }
+                println!("{line}");
+            }
+            Ok(())
+        }
+        Command::Current => {
+            let active = active_version(&config, &std::env::current_dir()?)?
+                .ok_or(AppError::new("no version is in use yet"))?;
+            println!("{}", active.version);
+            match active.source {
+                VersionSource::Env => eprintln!("rnpm: from {VERSION_ENV}"),
+                VersionSource::Project { file } => {
+                    eprintln!("rnpm: from {}", file.to_string_lossy())
+                }
+                VersionSource::Default => {}
+            }
+            Ok(())
+        }
+        Command::Shims => {
+            let shim = std::env::current_exe()?
+                .with_file_name(format!("rnpm-shim{}", std::env::consts::EXE_SUFFIX));
+            if !shim.exists() {
+                return Err(AppError::new(format!(
+                    "{} is missing, install it with `cargo install --path rnpm-shim`",
+                    shim.to_string_lossy()
+                )));
+            }
+            let dir = install_shims(&config.node_dir, &shim)?;
+            println!("{}", dir.to_string_lossy());
+            eprintln!("rnpm: put this folder first on your PATH");
+            Ok(())
+        }
+        Command::Env {
+            shell,
+            version,
+            reset,
+        } => {
+            let shell = shells(shell.into_iter().collect())?[0];
+            let version = version
+                .map(|version| resolve_local(&config, &version))
+                .transpose()?;
+            let env = match (&version, reset) {
+                (Some(version), _) => {
+                    print!("{}", shell.set_env(VERSION_ENV, Some(version)));
+                    Some(version.clone())
+                }
+                (None, true) => {
+                    print!("{}", shell.set_env(VERSION_ENV, None));
+                    None
+                }
+                (None, false) => std::env::var(VERSION_ENV).ok(),
+            };
+
+            let mut first = vec![shims_dir(&config.node_dir)];
+            match select_version(&config, &std::env::current_dir()?, env) {
+                // through `current`, `rnpm use` reaches this shell before the next `cd`
+                Ok(Some(active)) if active.source == VersionSource::Default => {
+                    first.push(current_bin(&config.node_dir));
+                }
+                Ok(Some(active)) => {
+                    let node = binary_path(&config.node_dir.join(active.version), "node");
+                    first.extend(node.parent().map(Path::to_path_buf));
+                }
+                Ok(None) => {}
+                // the shims say so again when they're run
+                Err(e) => eprintln!("rnpm: {}", e.message),
+            }
+            let path = std::env::var_os("PATH").unwrap_or_default();
+            print!(
+                "{}",
+                shell.set_path(&env_paths(&config.node_dir, &first, &path))?
+            );
+            Ok(())
+        }
+        Command::Setup {
+            shell,
+            remove,
+            dry_run,
+        } => {
+            let home = dirs::home_dir().ok_or(AppError::new("no home folder"))?;
+            let rnpm = std::env::current_exe()?;
+            for shell in shells(shell)? {
+                let setup = shell::plan(&home, &config.node_dir, Some(&rnpm), shell)?;
+                let rc_file = setup.rc_file.to_string_lossy();
+                // zsh had the old PATH line in .zshenv, which changes too
+                let files = match &setup.legacy_rc_file {
+                    Some(legacy) => format!("{rc_file} and {}", legacy.to_string_lossy()),
+                    None => rc_file.to_string(),
+                };
+                match (remove, dry_run) {
+                    (false, true) => {
+                        println!("{}: add to {rc_file}", shell.name());
+                        if setup.legacy {
+                            let from = match &setup.legacy_rc_file {
+                                Some(legacy) => legacy.to_string_lossy(),
+                                None => rc_file.clone(),
+                            };
+                            println!(
+                                "and take the PATH line older versions of rnpm wrote out of {from}"
+                            );
+                        }
+                        println!("\n{}", setup.snippet);
+                    }
+                    (true, true) if setup.installed || setup.legacy => {
+                        println!("{}: take the rnpm lines out of {files}", shell.name())
+                    }
+                    (true, true) => println!("{}: nothing in {rc_file}", shell.name()),
+                    (false, false) => {
+                        shell::install(&home, &config.node_dir, Some(&rnpm), shell)?;
+                        println!("{}: updated {files}, open a new shell", shell.name());
+                    }
+                    (true, false) => {
+                        if shell::remove(&home, shell)? {
+                            println!("{}: cleaned up {files}", shell.name());
+                        } else {
+                            println!("{}: nothing in {rc_file}", shell.name());
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        Command::Which { version } => {
+            let version = match version {
+                Some(version) => resolve_local(&config, &version)?,
+                None => {
+                    active_version(&config, &std::env::current_dir()?)?
+                        .ok_or(AppError::new("no version is in use yet"))?
+                        .version
+                }
+            };
+            if !list_local(&config)?.contains(&version) {
+                return Err(AppError::new(format!("node {version} is not installed")));
+            }
+            let node = binary_path(&config.node_dir.join(&version), "node");
+            println!("{}", node.to_string_lossy());
+            Ok(())
+        }
+    }
+}
+
+/// Progress on stderr, results on stdout.
+struct Stderr;
+
+impl Progress for Stderr {
+    fn retry(&self, attempt: u32, retries: u32, e: &AppError) -> AppResult<()> {
+        eprintln!("rnpm: {}, retrying ({attempt}/{retries})", e.message);
+        Ok(())
+    }
+
+    fn mirror_failed(&self, mirror: &str, e: &AppError) -> AppResult<()> {
+        eprintln!("rnpm: {mirror}: {}", e.message);
+        Ok(())
+    }
+
+    fn download(&self, filename: &str, total: u64, progress: u64) -> AppResult<()> {
+        eprint!(
+            "\rdownloading {filename} {}%",
+            progress * 100 / total.max(1)
+        );
+        if progress >= total {
+            eprintln!();
+        }
+        Ok(std::io::stderr().flush()?)
+    }
+
+    fn unpacking(&self) -> AppResult<()> {
+        eprintln!("unpacking");
+        Ok(())
+    }
+}
+
+/// The shells named, the login shell when there are none.
+fn shells(names: Vec<String>) -> AppResult<Vec<Shell>> {
+    if names.is_empty() {
+        return Ok(vec![Shell::detect().ok_or(AppError::new(
+            "can't tell which shell this is, pick one with --shell",
+        ))?]);
+    }
+    names.iter().map(|name| Shell::from_str(name)).collect()
+}
+
+/// Versions are folders named like `v20.11.1`, accept `20.11.1` too.
+fn normalize(version: &str) -> String {
+    format!("v{}", version.trim().trim_start_matches('v'))
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("20.11.1"), "v20.11.1");
+    assert_eq!(normalize(" v18.19.1 "), "v18.19.1");
+}
@@ -0,0 +1,43 @@
+use sha2::{Digest, Sha256};
+
+/// Find the sha256 of `filename` in the content of a `SHASUMS256.txt` file.
+pub fn find_checksum(shasums: &str, filename: &str) -> Option<String> {
+    shasums.lines().find_map(|line| {
+        let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+        if name.trim() == filename {
+            Some(hash.to_lowercase())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn hex_digest(hasher: Sha256) -> String {
+    format!("{:x}", hasher.finalize())
+}
+
+#[test]
+fn test_find_checksum() {
+    let shasums = "\
+9f1c4b7bff3a9e4b3a1b0e6c3c0f0c2a0a3f7e0b6f0e1b2c3d4e5f60718293a4  node-v20.11.1-darwin-arm64.tar.xz
+ABCDEF0123456789abcdef0123456789abcdef0123456789abcdef0123456789  node-v20.11.1-linux-x64.tar.xz
+";
+    assert_eq!(
+        find_checksum(shasums, "node-v20.11.1-linux-x64.tar.xz").as_deref(),
+        Some("abcdef0123456789abcdef0123456789abcdef0123456789abcdef0123456789")
+    );
+    assert_eq!(
+        find_checksum(shasums, "node-v20.11.1-linux-x64.tar.gz"),
+        None
+    );
+}
+
+#[test]
+fn test_hex_digest() {
+    let mut hasher = Sha256::new();
+    hasher.update(b"abc");
+    assert_eq!(
+        hex_digest(hasher),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
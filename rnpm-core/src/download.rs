@@ -0,0 +1,270 @@
+use std::path::{Path, PathBuf};
+
+use reqwest::{header, Client, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+
+use crate::error::{AppError, AppResult};
+
+/// Download `url` into `part_path`, resuming from the bytes a previous attempt
+/// left on disk when the server honors `Range`, restarting from scratch otherwise.
+///
+/// `on_progress` receives `(total, progress)`. The returned hasher covers the whole file.
+pub async fn download_file<F>(
+    client: &Client,
+    url: &str,
+    part_path: &Path,
+    mut on_progress: F,
+) -> AppResult<Sha256>
+where
+    F: FnMut(u64, u64) -> AppResult<()>,
+{
+    if let Some(path) = local_path(url) {
+        return copy_file(&path, part_path, on_progress).await;
+    }
+
+    let mut existing = match tokio::fs::metadata(part_path).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={existing}-"));
+    }
+    let mut response = request.send().await.map_err(AppError::network)?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        existing = 0;
+        response = client.get(url).send().await.map_err(AppError::network)?;
+    }
+    let response = response.error_for_status().map_err(AppError::network)?;
+
+    let resumed = existing > 0
+        && response.status() == StatusCode::PARTIAL_CONTENT
+        && content_range_start(response.headers()) == Some(existing);
+    if !resumed {
+        existing = 0;
+    }
+
+    let mut hasher = Sha256::new();
+    let mut file = tokio::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(!resumed)
+        .open(part_path)
+        .await?;
+
+    if resumed {
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+    }
+
+    let total = existing + response.content_length().unwrap_or(0);
+    let mut progress = existing;
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(AppError::network)?;
+        progress += chunk.len() as u64;
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+        on_progress(total, progress)?;
+    }
+    file.flush().await?;
+
+    Ok(hasher)
+}
+
+/// Copy an archive from a `file://` mirror, there is nothing to resume.
+async fn copy_file<F>(path: &Path, part_path: &Path, mut on_progress: F) -> AppResult<Sha256>
+where
+    F: FnMut(u64, u64) -> AppResult<()>,
+{
+    let mut source = tokio::fs::File::open(path).await?;
+    let total = source.metadata().await?.len();
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(part_path)
+        .await?;
+
+    let mut hasher = Sha256::new();
+    let mut progress = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = source.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        progress += n as u64;
+        hasher.update(&buf[..n]);
+        file.write_all(&buf[..n]).await?;
+        on_progress(total, progress)?;
+    }
+    file.flush().await?;
+
+    Ok(hasher)
+}
+
+pub async fn fetch_bytes(client: &Client, url: &str) -> AppResult<Vec<u8>> {
+    if let Some(path) = local_path(url) {
+        return Ok(tokio::fs::read(path).await?);
+    }
+
+    let bytes = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(AppError::network)?
+        .bytes()
+        .await
+        .map_err(AppError::network)?;
+    Ok(bytes.to_vec())
+}
+
+pub async fn fetch_text(client: &Client, url: &str) -> AppResult<String> {
+    let bytes = fetch_bytes(client, url).await?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+pub async fn fetch_json<T>(client: &Client, url: &str) -> AppResult<T>
+where
+    T: DeserializeOwned,
+{
+    let text = fetch_text(client, url).await?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// The local path of a `file://` url, mirrors served from a directory.
+fn local_path(url: &str) -> Option<PathBuf> {
+    let url = Url::parse(url).ok()?;
+    if url.scheme() == "file" {
+        url.to_file_path().ok()
+    } else {
+        None
+    }
+}
+
+fn content_range_start(headers: &header::HeaderMap) -> Option<u64> {
+    let value = headers.get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    let (start, _) = range.split_once('-')?;
+    start.parse().ok()
+}
+
+/// Serves `body`, the first response cut after `cut` bytes, and `Range` requests only honored
+/// when `ranges` is set.
+#[cfg(test)]
+async fn serve_flaky(body: &'static [u8], cut: usize, ranges: bool) -> String {
+    use crate::test_server::{serve, Response};
+
+    let addr = serve(move |n, request| {
+        let start = request
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("range: bytes=")?
+                    .trim_end_matches('-')
+                    .parse::<usize>()
+                    .ok()
+            })
+            .filter(|_| ranges);
+        let response = match start {
+            Some(start) => Response::new("206 Partial Content", &body[start..]).header(
+                "Content-Range",
+                format!("bytes {start}-{}/{}", body.len() - 1, body.len()),
+            ),
+            None => Response::new("200 OK", body),
+        };
+        if n == 0 {
+            response.cut(cut)
+        } else {
+            response
+        }
+    })
+    .await;
+
+    format!("http://{addr}/node.tar.xz")
+}
+
+#[tokio::test]
+async fn test_download_resume() {
+    static BODY: &[u8] = &[7u8; 100_000];
+    let url = serve_flaky(BODY, 40_000, true).await;
+    let dir = tempfile::tempdir().unwrap();
+    let part = dir.path().join("node.tar.xz.part");
+    let client = Client::new();
+
+    assert!(download_file(&client, &url, &part, |_, _| Ok(()))
+        .await
+        .is_err());
+    assert_eq!(std::fs::metadata(&part).unwrap().len(), 40_000);
+
+    let mut first_progress = None;
+    let hasher = download_file(&client, &url, &part, |_, progress| {
+        first_progress.get_or_insert(progress);
+        Ok(())
+    })
+    .await
+    .unwrap();
+
+    assert!(first_progress.unwrap() > 40_000);
+    assert_eq!(std::fs::read(&part).unwrap(), BODY);
+    assert_eq!(hasher.finalize(), Sha256::digest(BODY));
+}
+
+#[tokio::test]
+async fn test_download_restart_without_range_support() {
+    static BODY: &[u8] = &[3u8; 100_000];
+    let url = serve_flaky(BODY, 40_000, false).await;
+    let dir = tempfile::tempdir().unwrap();
+    let part = dir.path().join("node.tar.xz.part");
+    let client = Client::new();
+
+    assert!(download_file(&client, &url, &part, |_, _| Ok(()))
+        .await
+        .is_err());
+
+    let hasher = download_file(&client, &url, &part, |_, _| Ok(()))
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&part).unwrap(), BODY);
+    assert_eq!(hasher.finalize(), Sha256::digest(BODY));
+}
+
+#[tokio::test]
+async fn test_download_from_local_mirror() {
+    let mirror = tempfile::tempdir().unwrap();
+    let body = vec![5u8; 200_000];
+    std::fs::write(mirror.path().join("node.tar.xz"), &body).unwrap();
+    let url = Url::from_file_path(mirror.path().join("node.tar.xz")).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let part = dir.path().join("node.tar.xz.part");
+    let mut last = (0, 0);
+    let hasher = download_file(&Client::new(), url.as_str(), &part, |total, progress| {
+        last = (total, progress);
+        Ok(())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(last, (200_000, 200_000));
+    assert_eq!(std::fs::read(&part).unwrap(), body);
+    assert_eq!(hasher.finalize(), Sha256::digest(&body));
+
+    let missing = Url::from_file_path(mirror.path().join("index.json")).unwrap();
+    assert!(fetch_text(&Client::new(), missing.as_str()).await.is_err());
+}
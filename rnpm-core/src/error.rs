@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Other,
+    Checksum,
+    Signature,
+    Cancelled,
+    Network,
+    /// Another rnpm process holds the lock on `node_dir`.
+    Busy,
+}
+
+#[derive(Debug)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new<T>(message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::with_kind(ErrorKind::Other, message)
+    }
+
+    pub fn with_kind<T>(kind: ErrorKind, message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Connection failures, timeouts and `5xx`/`429` responses are worth retrying.
+    pub fn network(error: reqwest::Error) -> Self {
+        let transient = match error.status() {
+            Some(status) => {
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            None => {
+                error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+            }
+        };
+        let kind = if transient {
+            ErrorKind::Network
+        } else {
+            ErrorKind::Other
+        };
+
+        Self::with_kind(kind, error.to_string())
+    }
+}
+
+impl<T> From<T> for AppError
+where
+    T: std::error::Error,
+{
+    fn from(value: T) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl serde::Serialize for AppError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.message.as_ref())
+    }
+}
+
+pub type AppResult<T> = Result<T, AppError>;
@@ -0,0 +1,79 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppResult, node::Node};
+
+/// The last `index.json` fetched from a mirror, kept on disk so the list works offline.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct IndexCache {
+    /// Seconds since the unix epoch.
+    pub fetched_at: u64,
+    pub nodes: Vec<Node>,
+}
+
+impl IndexCache {
+    pub fn new(nodes: Vec<Node>) -> Self {
+        Self {
+            fetched_at: now(),
+            nodes,
+        }
+    }
+
+    /// `None` when there is no cache yet or it can't be read.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_index_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".cache/index.json");
+    assert!(IndexCache::load(&path).is_none());
+
+    let nodes = serde_json::from_str::<Vec<Node>>(
+        r#"[
+            {"version": "v20.11.1", "lts": "Iron"},
+            {"version": "v21.6.2", "lts": false}
+        ]"#,
+    )
+    .unwrap();
+    let mut cache = IndexCache::new(nodes);
+    cache.fetched_at -= 7200;
+    cache.save(&path).unwrap();
+
+    let cache = IndexCache::load(&path).unwrap();
+    assert_eq!(cache.nodes.len(), 2);
+    assert_eq!(cache.nodes[0].lts.as_deref(), Some("Iron"));
+    assert_eq!(cache.nodes[1].lts, None);
+    assert!(cache.age() >= Duration::from_secs(7200));
+
+    std::fs::write(&path, "not json").unwrap();
+    assert!(IndexCache::load(&path).is_none());
+}
@@ -0,0 +1,25 @@
+//! What the rnpm app and the `rnpm` command line share: settings, the node list, downloads,
+//! verification and the layout of `node_dir`.
+//!
+//! `manager` is the plain async API both are thin adapters over, the other modules are its
+//! building blocks.
+
+pub mod checksum;
+pub mod config;
+pub mod download;
+pub mod error;
+pub mod index_cache;
+pub mod lock;
+pub mod manager;
+pub mod node;
+pub mod project;
+pub mod queue;
+pub mod resolve;
+pub mod retry;
+pub mod shell;
+pub mod shim;
+pub mod signature;
+#[cfg(test)]
+mod test_server;
+pub mod unpack;
+pub mod verify;
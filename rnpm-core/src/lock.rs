@@ -0,0 +1,174 @@
+use std::{
+    fs::File,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use fs4::FileExt;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+
+/// The file lock is per open file, this keeps the tasks of one process from locking each
+/// other out.
+static PROCESS: Mutex<()> = Mutex::const_new(());
+
+/// An advisory lock on `node_dir/.lock`, held by whichever rnpm process is changing what's
+/// installed. Released on drop.
+pub struct HomeLock {
+    file: File,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl HomeLock {
+    /// Wait up to `timeout` for other processes to finish, failing with `ErrorKind::Busy`.
+    pub async fn acquire(dir: &Path, timeout: Duration) -> AppResult<Self> {
+        let guard = PROCESS.lock().await;
+        let file = lock_file(&dir.join(".lock"), timeout, || {
+            format!(
+                "another rnpm process is busy with {}, try again later",
+                dir.to_string_lossy()
+            )
+        })
+        .await?;
+        Ok(Self {
+            file,
+            _guard: guard,
+        })
+    }
+}
+
+/// An advisory lock on `cache_dir/{version}.lock`, held while `version` is downloaded so two
+/// installs never write the same `.part` file. Downloads of other versions go on meanwhile.
+pub struct DownloadLock {
+    file: File,
+}
+
+impl DownloadLock {
+    /// Wait up to `timeout` for another download of `version` to finish, failing with
+    /// `ErrorKind::Busy`.
+    pub async fn acquire(cache_dir: &Path, version: &str, timeout: Duration) -> AppResult<Self> {
+        let file = lock_file(&cache_dir.join(format!("{version}.lock")), timeout, || {
+            format!("another rnpm process is downloading {version}, try again later")
+        })
+        .await?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for DownloadLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// Open `path` and take an exclusive lock on it, polling until `timeout`.
+async fn lock_file<F>(path: &Path, timeout: Duration, busy: F) -> AppResult<File>
+where
+    F: FnOnce() -> String,
+{
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(file),
+            Err(e) if e.kind() != fs4::lock_contended_error().kind() => return Err(e.into()),
+            Err(_) if started.elapsed() >= timeout => {
+                return Err(AppError::with_kind(ErrorKind::Busy, busy()))
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+}
+
+impl Drop for HomeLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_home_lock_across_processes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join(".lock"), "").unwrap();
+
+    // another process holding the lock, flock(1) uses flock(2) like we do and
+    // --no-fork makes killing it release the lock
+    let mut holder = std::process::Command::new("flock")
+        .arg("--no-fork")
+        .arg(dir.path().join(".lock"))
+        .args(["-c", "echo locked; exec sleep 30"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufRead::read_line(
+        &mut std::io::BufReader::new(holder.stdout.as_mut().unwrap()),
+        &mut line,
+    )
+    .unwrap();
+    assert_eq!(line.trim(), "locked");
+
+    let err = HomeLock::acquire(dir.path(), Duration::from_millis(300))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, ErrorKind::Busy);
+
+    holder.kill().unwrap();
+    holder.wait().unwrap();
+    let lock = HomeLock::acquire(dir.path(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    // and the other way around
+    let status = std::process::Command::new("flock")
+        .args(["--nonblock", "--conflict-exit-code", "9"])
+        .arg(dir.path().join(".lock"))
+        .arg("true")
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(9));
+
+    drop(lock);
+    let status = std::process::Command::new("flock")
+        .arg("--nonblock")
+        .arg(dir.path().join(".lock"))
+        .arg("true")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn test_download_lock_per_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let lock = DownloadLock::acquire(dir.path(), "v20.11.1", Duration::from_secs(1))
+        .await
+        .unwrap();
+
+    // the lock is per open file, so even this process waits for it
+    let err = DownloadLock::acquire(dir.path(), "v20.11.1", Duration::from_millis(200))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.kind, ErrorKind::Busy);
+    DownloadLock::acquire(dir.path(), "v18.19.1", Duration::from_millis(200))
+        .await
+        .unwrap();
+
+    drop(lock);
+    DownloadLock::acquire(dir.path(), "v20.11.1", Duration::from_millis(200))
+        .await
+        .unwrap();
+}
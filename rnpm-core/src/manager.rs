@@ -0,0 +1,604 @@
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use reqwest::Client;
+use semver::Version;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    checksum::{find_checksum, hex_digest},
+    config::Config,
+    download::{download_file, fetch_text},
+    error::{AppError, AppResult, ErrorKind},
+    index_cache::IndexCache,
+    lock::{DownloadLock, HomeLock},
+    node::{current_version, load_list, local_versions, set_current_version, Node, NodeList},
+    project::{find_project_version, ProjectVersion},
+    resolve::{resolve, Spec},
+    retry::retry,
+    signature::fetch_verified_shasums,
+    unpack::unpack,
+};
+
+/// What the app and the command line show while the operations below run. Returning an
+/// error from any of these aborts the operation.
+pub trait Progress {
+    /// A transient failure, attempt `attempt` out of `retries` starts after a backoff.
+    fn retry(&self, _attempt: u32, _retries: u32, _e: &AppError) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// `mirror` failed, the next one is tried.
+    fn mirror_failed(&self, _mirror: &str, _e: &AppError) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// `progress` of `total` bytes of `filename` are downloaded.
+    fn download(&self, _filename: &str, _total: u64, _progress: u64) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// The download is verified and being unpacked into `node_dir`.
+    fn unpacking(&self) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+/// No progress reporting.
+impl Progress for () {}
+
+/// The versions available for download, see `load_list`.
+pub async fn list_remote<P: Progress>(
+    config: &Config,
+    client: &Client,
+    refresh: bool,
+    progress: &P,
+) -> AppResult<NodeList> {
+    load_list(config, client, refresh, |attempt, retries, e: &AppError| {
+        progress.retry(attempt, retries, e)
+    })
+    .await
+}
+
+/// The installed versions, newest first.
+pub fn list_local(config: &Config) -> AppResult<Vec<String>> {
+    let mut versions = local_versions(&config.node_dir)?;
+    versions
+        .sort_by_key(|version| Reverse(Version::from_str(version.trim_start_matches('v')).ok()));
+    Ok(versions)
+}
+
+/// The installed versions as nodes, newest first, with the LTS line the cached list knows
+/// them by so that `lts/*` and friends resolve without the network.
+pub fn local_nodes(config: &Config) -> AppResult<Vec<Node>> {
+    installed_nodes(config, true)
+}
+
+/// `local_nodes`, only reading the cached list for the LTS lines when `lts`, the shims resolve
+/// through here on every run.
+fn installed_nodes(config: &Config, lts: bool) -> AppResult<Vec<Node>> {
+    let cached = lts
+        .then(|| IndexCache::load(&config.index_cache_path()))
+        .flatten()
+        .map(|cache| cache.nodes)
+        .unwrap_or_default();
+    Ok(list_local(config)?
+        .into_iter()
+        .map(|version| Node {
+            lts: cached
+                .iter()
+                .find(|node| node.version == version)
+                .and_then(|node| node.lts.clone()),
+            version,
+            files: vec![],
+            available: true,
+        })
+        .collect())
+}
+
+/// The newest remote version `spec` matches, see `Spec`. Exact versions don't need the list.
+pub async fn resolve_remote<P: Progress>(
+    config: &Config,
+    client: &Client,
+    spec: &str,
+    progress: &P,
+) -> AppResult<String> {
+    if let Spec::Exact(version) = Spec::from_str(spec)? {
+        return Ok(format!("v{version}"));
+    }
+    let list = list_remote(config, client, false, progress).await?;
+    resolve(spec, &list.nodes)
+}
+
+/// The newest installed version `spec` matches, see `Spec`.
+pub fn resolve_local(config: &Config, spec: &str) -> AppResult<String> {
+    let lts = Spec::from_str(spec)?.needs_lts();
+    resolve(spec, &installed_nodes(config, lts)?)
+}
+
+/// What the project around a folder pins and the versions satisfying it.
+#[derive(Serialize, Debug)]
+pub struct Project {
+    #[serde(flatten)]
+    pub version: ProjectVersion,
+    /// The newest installed version that does.
+    pub installed: Option<String>,
+    /// The newest remote version that does.
+    pub available: Option<String>,
+    /// Why the pinned version can't be read, an unknown alias for one.
+    pub error: Option<String>,
+}
+
+/// See `find_project_version`, `remote` is the list to look for `Project::available` in.
+pub fn project(config: &Config, dir: &Path, remote: &[Node]) -> AppResult<Option<Project>> {
+    let Some(version) = find_project_version(dir)? else {
+        return Ok(None);
+    };
+
+    let (installed, available, error) = match Spec::from_str(&version.spec) {
+        Ok(spec) => {
+            let local = local_nodes(config)?;
+            let newest = |nodes| {
+                spec.matching(nodes)
+                    .first()
+                    .map(|node: &&Node| node.version.clone())
+            };
+            (newest(&local), newest(remote), None)
+        }
+        Err(e) => (None, None, Some(e.message)),
+    };
+
+    Ok(Some(Project {
+        version,
+        installed,
+        available,
+        error,
+    }))
+}
+
+/// Overrides the version the shims run, a version, range or alias like in `.nvmrc`.
+pub const VERSION_ENV: &str = "RNPM_NODE_VERSION";
+
+/// Why a version is the one to run.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VersionSource {
+    /// `RNPM_NODE_VERSION`.
+    Env,
+    /// A project file, see `find_project_version`.
+    Project { file: PathBuf },
+    /// The one picked with `set_current`.
+    Default,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct ActiveVersion {
+    pub version: String,
+    pub source: VersionSource,
+}
+
+/// The installed version to run in `dir`: `RNPM_NODE_VERSION`, then the version the project
+/// around `dir` pins, then the one picked with `set_current`. `None` when there is none at all.
+pub fn active_version(config: &Config, dir: &Path) -> AppResult<Option<ActiveVersion>> {
+    select_version(config, dir, std::env::var(VERSION_ENV).ok())
+}
+
+/// `active_version` with `env` in place of `RNPM_NODE_VERSION`, for code about to change it.
+pub fn select_version(
+    config: &Config,
+    dir: &Path,
+    env: Option<String>,
+) -> AppResult<Option<ActiveVersion>> {
+    if let Some(spec) = env.filter(|spec| !spec.trim().is_empty()) {
+        let version = resolve_local(config, &spec)
+            .map_err(|e| AppError::new(format!("{VERSION_ENV}={spec}: {}", e.message)))?;
+        return Ok(Some(ActiveVersion {
+            version,
+            source: VersionSource::Env,
+        }));
+    }
+
+    if let Some(project) = find_project_version(dir)? {
+        let version = resolve_local(config, &project.spec).map_err(|e| {
+            AppError::new(format!(
+                "{} wants {}: {}, install it with `rnpm install {}`",
+                project.file.to_string_lossy(),
+                project.spec,
+                e.message,
+                project.spec
+            ))
+        })?;
+        return Ok(Some(ActiveVersion {
+            version,
+            source: VersionSource::Project { file: project.file },
+        }));
+    }
+
+    Ok(current(config)?.map(|version| ActiveVersion {
+        version,
+        source: VersionSource::Default,
+    }))
+}
+
+/// The version in use, `None` before one was ever picked.
+pub fn current(config: &Config) -> AppResult<Option<String>> {
+    current_version(&config.node_dir)
+}
+
+/// Make an installed version the one in use.
+pub async fn set_current(config: &Config, version: &str) -> AppResult<()> {
+    ensure_installed(config, version)?;
+    let _lock = HomeLock::acquire(&config.node_dir, config.lock_timeout()).await?;
+    set_current_version(&config.node_dir, version)
+}
+
+/// Download `version` from the first mirror that serves a file matching its `SHASUMS256.txt`
+/// and unpack it into `node_dir`.
+pub async fn install<P: Progress>(
+    config: &Config,
+    client: &Client,
+    version: &str,
+    progress: &P,
+    cancel: &CancellationToken,
+) -> AppResult<()> {
+    install_version(config, client, version, progress, cancel, false).await
+}
+
+/// `install` over the install there is, which stays in place until the new one is unpacked.
+pub async fn reinstall<P: Progress>(
+    config: &Config,
+    client: &Client,
+    version: &str,
+    progress: &P,
+    cancel: &CancellationToken,
+) -> AppResult<()> {
+    install_version(config, client, version, progress, cancel, true).await
+}
+
+async fn install_version<P: Progress>(
+    config: &Config,
+    client: &Client,
+    version: &str,
+    progress: &P,
+    cancel: &CancellationToken,
+    replace: bool,
+) -> AppResult<()> {
+    let cancelled = || {
+        AppError::with_kind(
+            ErrorKind::Cancelled,
+            format!("download of {version} cancelled"),
+        )
+    };
+
+    let cache_dir = config.cache_dir();
+    // another process may be installing the same version, wait for it and look again
+    let _download = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => return Err(cancelled()),
+        lock = DownloadLock::acquire(&cache_dir, version, config.lock_timeout()) => lock?,
+    };
+    if !replace && list_local(config)?.iter().any(|v| v == version) {
+        return Err(AppError::new(format!(
+            "node {version} is already installed"
+        )));
+    }
+
+    let on_retry = |attempt, retries, e: &AppError| progress.retry(attempt, retries, e);
+
+    // the list tells which archives this platform has, go on without it when offline
+    let list = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => return Err(cancelled()),
+        list = load_list(config, client, false, on_retry) => list,
+    };
+    let files = match list {
+        Ok(list) => {
+            list.nodes
+                .into_iter()
+                .find(|node| node.version == version)
+                .ok_or(AppError::new(format!("unknown version {version}")))?
+                .files
+        }
+        Err(_) => vec![],
+    };
+    let filenames = config.archives(version, &files);
+    if filenames.is_empty() {
+        return Err(AppError::new(format!(
+            "{version} has no build for {}-{}",
+            config.platform, config.arch
+        )));
+    }
+    let policy = config.retry_policy();
+    let mut res = Err(AppError::new("no node mirror configured"));
+    for mirror in config.mirrors() {
+        res = async {
+            let shasums_url = config.shasums_url(&mirror, version);
+            let shasums = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Err(cancelled()),
+                res = retry(&policy, on_retry, || async {
+                    if config.verify_signature {
+                        fetch_verified_shasums(client, &shasums_url, &config.keyring_dir()).await
+                    } else {
+                        fetch_text(client, &shasums_url).await
+                    }
+                }) => res?,
+            };
+            // the best format this mirror actually has
+            let (filename, checksum) = filenames
+                .iter()
+                .find_map(|filename| Some((filename, find_checksum(&shasums, filename)?)))
+                .ok_or(AppError::with_kind(
+                    ErrorKind::Checksum,
+                    format!("no checksum found for {}", filenames.join(" or ")),
+                ))?;
+
+            let url = config.download_url(&mirror, version, filename);
+            let part_path = cache_dir.join(format!("{filename}.part"));
+            let hasher = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    let _ = tokio::fs::remove_file(&part_path).await;
+                    return Err(cancelled());
+                }
+                res = retry(&policy, on_retry, || {
+                    download_file(client, &url, &part_path, |total, downloaded| {
+                        progress.download(filename, total, downloaded)
+                    })
+                }) => res?,
+            };
+
+            let digest = hex_digest(hasher);
+            if digest != checksum {
+                tokio::fs::remove_file(&part_path).await?;
+                return Err(AppError::with_kind(
+                    ErrorKind::Checksum,
+                    format!("checksum mismatch for {filename}: expected {checksum}, got {digest}"),
+                ));
+            }
+
+            Ok(filename.clone())
+        }
+        .await;
+
+        match &res {
+            Err(e) if e.kind != ErrorKind::Cancelled => progress.mirror_failed(&mirror, e)?,
+            _ => break,
+        }
+    }
+    let filename = res?;
+    let file_path = cache_dir.join(&filename);
+    tokio::fs::rename(cache_dir.join(format!("{filename}.part")), &file_path).await?;
+
+    progress.unpacking()?;
+    let _lock = HomeLock::acquire(&config.node_dir, config.lock_timeout()).await?;
+    let res = tokio::task::spawn_blocking({
+        let version = version.to_string();
+        let file_path = file_path.clone();
+        let node_dir = config.node_dir.clone();
+        move || unpack(version, file_path, node_dir)
+    })
+    .await;
+    tokio::fs::remove_file(&file_path).await?;
+
+    res?
+}
+
+/// Remove an installed version, even when it's the one in use.
+pub async fn uninstall(config: &Config, version: &str) -> AppResult<()> {
+    ensure_installed(config, version)?;
+    let _lock = HomeLock::acquire(&config.node_dir, config.lock_timeout()).await?;
+    tokio::fs::remove_dir_all(config.node_dir.join(version)).await?;
+    Ok(())
+}
+
+fn ensure_installed(config: &Config, version: &str) -> AppResult<()> {
+    if local_versions(&config.node_dir)?
+        .iter()
+        .any(|v| v == version)
+    {
+        Ok(())
+    } else {
+        Err(AppError::new(format!("node {version} is not installed")))
+    }
+}
+
+#[tokio::test]
+async fn test_install_from_local_mirror() {
+    use std::sync::Mutex;
+
+    use sha2::{Digest, Sha256};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Progress for Recorder {
+        fn download(&self, filename: &str, total: u64, progress: u64) -> AppResult<()> {
+            if total == progress {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("downloaded {filename}"));
+            }
+            Ok(())
+        }
+
+        fn unpacking(&self) -> AppResult<()> {
+            self.0.lock().unwrap().push("unpacking".to_string());
+            Ok(())
+        }
+    }
+
+    let filename = "node-v20.11.1-linux-x64.tar.xz";
+    let archive = std::fs::read(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/unpack")
+            .join(filename),
+    )
+    .unwrap();
+
+    let mirror = tempfile::tempdir().unwrap();
+    std::fs::write(
+        mirror.path().join("index.json"),
+        r#"[{"version":"v20.11.1","lts":"Iron","files":["linux-x64"]}]"#,
+    )
+    .unwrap();
+    let dist = mirror.path().join("v20.11.1");
+    std::fs::create_dir_all(&dist).unwrap();
+    std::fs::write(dist.join(filename), &archive).unwrap();
+    std::fs::write(
+        dist.join("SHASUMS256.txt"),
+        format!(
+            "{}  {filename}\n",
+            hex_digest(Sha256::new_with_prefix(&archive))
+        ),
+    )
+    .unwrap();
+
+    let home = tempfile::tempdir().unwrap();
+    let config = Config {
+        mirrors: vec![reqwest::Url::from_directory_path(mirror.path())
+            .unwrap()
+            .to_string()],
+        node_dir: home.path().to_path_buf(),
+        platform: "linux".to_string(),
+        arch: "x64".to_string(),
+        ..Config::default()
+    };
+    let client = Client::new();
+
+    let list = list_remote(&config, &client, false, &()).await.unwrap();
+    assert_eq!(list.nodes.len(), 1);
+    assert!(list.nodes[0].available);
+    assert_eq!(list.cache_error, None);
+
+    assert_eq!(
+        resolve_remote(&config, &client, "lts/iron", &())
+            .await
+            .unwrap(),
+        "v20.11.1"
+    );
+    assert!(resolve_local(&config, "20").is_err());
+
+    let progress = Recorder::default();
+    let cancel = CancellationToken::new();
+    install(&config, &client, "v20.11.1", &progress, &cancel)
+        .await
+        .unwrap();
+    assert_eq!(
+        *progress.0.lock().unwrap(),
+        [format!("downloaded {filename}"), "unpacking".to_string()]
+    );
+    assert_eq!(list_local(&config).unwrap(), ["v20.11.1"]);
+    assert_eq!(resolve_local(&config, "lts/*").unwrap(), "v20.11.1");
+
+    let checkout = tempfile::tempdir().unwrap();
+    std::fs::write(checkout.path().join(".nvmrc"), "lts/iron\n").unwrap();
+    let pinned = project(&config, checkout.path(), &list.nodes)
+        .unwrap()
+        .unwrap();
+    assert_eq!(pinned.installed.as_deref(), Some("v20.11.1"));
+    assert_eq!(pinned.available.as_deref(), Some("v20.11.1"));
+    std::fs::write(checkout.path().join(".nvmrc"), "lts/argon\n").unwrap();
+    let pinned = project(&config, checkout.path(), &list.nodes)
+        .unwrap()
+        .unwrap();
+    assert_eq!((pinned.installed, pinned.available), (None, None));
+    assert!(!config.cache_dir().join(filename).exists());
+
+    let err = install(&config, &client, "v20.11.1", &(), &cancel)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("already installed"));
+    let err = install(&config, &client, "v21.6.2", &(), &cancel)
+        .await
+        .unwrap_err();
+    assert!(err.message.contains("unknown version"));
+
+    uninstall(&config, "v20.11.1").await.unwrap();
+    assert!(list_local(&config).unwrap().is_empty());
+    assert!(uninstall(&config, "v20.11.1").await.is_err());
+
+    // a list that can't be cached still comes back, saying so
+    let node_dir = home.path().join("not a folder");
+    std::fs::write(&node_dir, "").unwrap();
+    let config = Config { node_dir, ..config };
+    let list = list_remote(&config, &client, true, &()).await.unwrap();
+    assert_eq!(list.nodes.len(), 1);
+    assert!(list.cache_error.unwrap().starts_with("can't cache"));
+}
+
+#[tokio::test]
+async fn test_install_cancelled_while_retrying() {
+    let home = tempfile::tempdir().unwrap();
+    let config = Config {
+        // nothing listens there, every request fails and is retried
+        mirrors: vec!["http://127.0.0.1:1/".to_string()],
+        node_dir: home.path().to_path_buf(),
+        retries: 10,
+        retry_delay_ms: 1000,
+        ..Config::default()
+    };
+    let cancel = CancellationToken::new();
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            cancel.cancel();
+        }
+    });
+
+    let started = std::time::Instant::now();
+    let err = install(&config, &Client::new(), "v20.11.1", &(), &cancel)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Cancelled);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+}
+
+#[test]
+fn test_select_version() {
+    let home = tempfile::tempdir().unwrap();
+    for version in ["v18.19.1", "v20.11.1"] {
+        std::fs::create_dir_all(home.path().join(version)).unwrap();
+    }
+    let config = Config {
+        node_dir: home.path().to_path_buf(),
+        ..Config::default()
+    };
+    let checkout = tempfile::tempdir().unwrap();
+    let select =
+        |env: Option<&str>| select_version(&config, checkout.path(), env.map(str::to_string));
+
+    assert_eq!(select(None).unwrap(), None);
+    std::fs::write(home.path().join("version"), "v20.11.1").unwrap();
+    assert_eq!(
+        select(None).unwrap().unwrap(),
+        ActiveVersion {
+            version: "v20.11.1".to_string(),
+            source: VersionSource::Default
+        }
+    );
+
+    std::fs::write(checkout.path().join(".node-version"), "18").unwrap();
+    assert_eq!(
+        select(None).unwrap().unwrap(),
+        ActiveVersion {
+            version: "v18.19.1".to_string(),
+            source: VersionSource::Project {
+                file: checkout.path().join(".node-version")
+            }
+        }
+    );
+    assert_eq!(select(Some("20")).unwrap().unwrap().version, "v20.11.1");
+    assert_eq!(select(Some(" ")).unwrap().unwrap().version, "v18.19.1");
+
+    std::fs::write(checkout.path().join(".node-version"), "16").unwrap();
+    let err = select(None).unwrap_err();
+    assert!(err.message.contains("rnpm install 16"), "{}", err.message);
+    assert!(select(Some("21")).is_err());
+}
@@ -0,0 +1,326 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use reqwest::Client;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    download::fetch_json,
+    error::{AppError, AppResult},
+    index_cache::IndexCache,
+    retry::retry,
+};
+
+mod lts {
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    #[serde(untagged)]
+    enum Lts {
+        Bool(bool),
+        Str(String),
+        // what a cached list serializes `None` to
+        Null(()),
+    }
+
+    impl From<Lts> for Option<String> {
+        fn from(status: Lts) -> Self {
+            match status {
+                Lts::Bool(_) | Lts::Null(_) => None,
+                Lts::Str(x) => Some(x),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Lts::deserialize(deserializer)?.into())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Node {
+    pub version: String,
+    #[serde(deserialize_with = "lts::deserialize")]
+    pub lts: Option<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Whether there is a build for this platform, worked out from `files`.
+    #[serde(skip_deserializing)]
+    pub available: bool,
+}
+
+/// The remote list and how old it is.
+pub struct NodeList {
+    pub nodes: Vec<Node>,
+    /// Seconds since the unix epoch.
+    pub fetched_at: u64,
+    /// Why the mirrors couldn't be reached when `nodes` comes from a stale cache.
+    pub error: Option<String>,
+    /// Why a freshly fetched list couldn't be written to the cache, the list itself is fine.
+    pub cache_error: Option<String>,
+}
+
+/// The list from the on-disk cache while it's younger than `list_ttl`, from the mirrors
+/// otherwise, falling back to the stale cache when they can't be reached.
+pub async fn load_list<R>(
+    config: &Config,
+    client: &Client,
+    refresh: bool,
+    on_retry: R,
+) -> AppResult<NodeList>
+where
+    R: FnMut(u32, u32, &AppError) -> AppResult<()>,
+{
+    let path = config.index_cache_path();
+    let cached = IndexCache::load(&path);
+    let mut error = None;
+    let mut cache_error = None;
+
+    let cache = match cached {
+        Some(cache) if !refresh && cache.age() < config.list_ttl() => cache,
+        cached => match fetch_list(config, client, on_retry).await {
+            Ok(nodes) => {
+                let cache = IndexCache::new(nodes);
+                if let Err(e) = cache.save(&path) {
+                    cache_error = Some(format!(
+                        "can't cache {}: {}",
+                        path.to_string_lossy(),
+                        e.message
+                    ));
+                }
+                cache
+            }
+            // offline, keep going with whatever was fetched last
+            Err(e) => match cached {
+                Some(cache) => {
+                    error = Some(e.message);
+                    cache
+                }
+                None => return Err(e),
+            },
+        },
+    };
+
+    let mut nodes = cache.nodes;
+    mark_available(config, &mut nodes);
+    Ok(NodeList {
+        nodes,
+        fetched_at: cache.fetched_at,
+        error,
+        cache_error,
+    })
+}
+
+/// `index.json` from the first mirror that serves it, without the versions rnpm can't install.
+pub async fn fetch_list<R>(
+    config: &Config,
+    client: &Client,
+    mut on_retry: R,
+) -> AppResult<Vec<Node>>
+where
+    R: FnMut(u32, u32, &AppError) -> AppResult<()>,
+{
+    let policy = config.retry_policy();
+
+    let mut res = Err(AppError::new("no node mirror configured"));
+    for mirror in config.mirrors() {
+        let url = config.list_url(&mirror);
+        res = retry(&policy, &mut on_retry, || {
+            fetch_json::<Vec<Node>>(client, &url)
+        })
+        .await;
+        if res.is_ok() {
+            break;
+        }
+    }
+
+    let res = res?
+        .into_iter()
+        .filter(|node| {
+            let mut res = false;
+            if let Ok(version) = Version::from_str(node.version.trim_start_matches('v')) {
+                if version.major >= 4 {
+                    res = true;
+                } else {
+                    let minors = [10u64, 12u64];
+                    if version.major == 0 && minors.contains(&version.minor) {
+                        res = true;
+                    }
+                }
+            }
+
+            res
+        })
+        .collect::<Vec<_>>();
+
+    Ok(res)
+}
+
+/// Work out `Node::available`, the settings deciding it may change after the list is loaded.
+pub fn mark_available(config: &Config, nodes: &mut [Node]) {
+    for node in nodes.iter_mut() {
+        node.available = !config.archives(&node.version, &node.files).is_empty();
+    }
+}
+
+/// The versions installed in `node_dir`, folders named like `v20.11.1`.
+pub fn local_versions(node_dir: &Path) -> AppResult<Vec<String>> {
+    std::fs::create_dir_all(node_dir)?;
+
+    let mut local_versions = vec![];
+    for entry in std::fs::read_dir(node_dir)? {
+        let entry = entry?;
+        if entry.metadata()?.is_dir() {
+            if let Ok(dir_name) = entry.file_name().into_string() {
+                let version = dir_name.trim_start_matches('v');
+                if Version::from_str(version).is_ok() {
+                    local_versions.push(dir_name);
+                }
+            }
+        }
+    }
+
+    Ok(local_versions)
+}
+
+/// The version recorded in `node_dir/version`, `None` before one was ever picked.
+pub fn current_version(node_dir: &Path) -> AppResult<Option<String>> {
+    let version = match std::fs::read_to_string(node_dir.join("version")) {
+        Ok(version) => version,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let version = version.trim();
+    if Version::from_str(version.trim_start_matches('v')).is_ok() {
+        Ok(Some(version.to_string()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Record `version` as the current one and point `current_link` at it.
+pub fn set_current_version(node_dir: &Path, version: &str) -> AppResult<()> {
+    std::fs::create_dir_all(node_dir)?;
+    std::fs::write(node_dir.join("version"), version)?;
+    link_current_version(node_dir, version)
+}
+
+/// `node_dir/current`, following the current version, so `PATH` never has to change.
+pub fn current_link(node_dir: &Path) -> PathBuf {
+    node_dir.join("current")
+}
+
+/// The folder with `node` in it through `current_link`, what goes on `PATH`.
+pub fn current_bin(node_dir: &Path) -> PathBuf {
+    let node = binary_path(&current_link(node_dir), "node");
+    node.parent().map(Path::to_path_buf).unwrap_or(node)
+}
+
+/// Point `current_link` at `version` by renaming a new link over the old one, so shells already
+/// running never see it missing. Windows can't rename a junction over another, there the old one
+/// goes first.
+pub fn link_current_version(node_dir: &Path, version: &str) -> AppResult<()> {
+    let link = current_link(node_dir);
+    let staging = node_dir.join(format!(".current-{}", std::process::id()));
+    match std::fs::remove_file(&staging) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    #[cfg(unix)]
+    {
+        // relative, moving `node_dir` keeps it working
+        std::os::unix::fs::symlink(version, &staging)?;
+        std::fs::rename(&staging, &link)?;
+    }
+
+    #[cfg(windows)]
+    {
+        junction::create(node_dir.join(version), &staging)?;
+        match std::fs::remove_dir(&link) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        std::fs::rename(&staging, &link)?;
+    }
+
+    Ok(())
+}
+
+/// Where an install keeps `name`, `node` itself or one of the scripts next to it.
+pub fn binary_path(dir: &Path, name: &str) -> PathBuf {
+    #[cfg(unix)]
+    {
+        dir.join("bin").join(name)
+    }
+
+    #[cfg(windows)]
+    {
+        if name == "node" {
+            dir.join("node.exe")
+        } else {
+            dir.join(format!("{name}.cmd"))
+        }
+    }
+}
+
+#[test]
+fn test_local_and_current_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["v20.11.1", "v18.19.1", ".cache", ".staging-v21.6.2", "keys"] {
+        std::fs::create_dir_all(dir.path().join(name)).unwrap();
+    }
+    std::fs::write(dir.path().join("v16.20.2"), "not a folder").unwrap();
+
+    let mut versions = local_versions(dir.path()).unwrap();
+    versions.sort();
+    assert_eq!(versions, ["v18.19.1", "v20.11.1"]);
+
+    assert_eq!(current_version(dir.path()).unwrap(), None);
+    std::fs::write(dir.path().join("version"), "v20.11.1\n").unwrap();
+    assert_eq!(
+        current_version(dir.path()).unwrap().as_deref(),
+        Some("v20.11.1")
+    );
+    std::fs::write(dir.path().join("version"), "").unwrap();
+    assert_eq!(current_version(dir.path()).unwrap(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_set_current_version() {
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["v20.11.1", "v18.19.1"] {
+        std::fs::create_dir_all(dir.path().join(version).join("bin")).unwrap();
+        std::fs::write(dir.path().join(version).join("bin/node"), version).unwrap();
+    }
+
+    set_current_version(dir.path(), "v20.11.1").unwrap();
+    let node = binary_path(&current_link(dir.path()), "node");
+    assert_eq!(std::fs::read_to_string(&node).unwrap(), "v20.11.1");
+    assert_eq!(current_bin(dir.path()), dir.path().join("current/bin"));
+
+    set_current_version(dir.path(), "v18.19.1").unwrap();
+    assert_eq!(std::fs::read_to_string(&node).unwrap(), "v18.19.1");
+    assert_eq!(
+        current_version(dir.path()).unwrap().as_deref(),
+        Some("v18.19.1")
+    );
+    assert_eq!(
+        std::fs::read_link(current_link(dir.path())).unwrap(),
+        Path::new("v18.19.1")
+    );
+    // no staging link left behind, and `current` isn't taken for a version
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 4);
+    let mut versions = local_versions(dir.path()).unwrap();
+    versions.sort();
+    assert_eq!(versions, ["v18.19.1", "v20.11.1"]);
+}
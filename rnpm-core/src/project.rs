@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// The node version a project pins, as written, see `resolve::Spec`.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct ProjectVersion {
+    /// The folder holding `file`.
+    pub dir: PathBuf,
+    pub file: PathBuf,
+    /// A version, range or alias like `18.19.0`, `>=18 <21` or `lts/hydrogen`.
+    pub spec: String,
+}
+
+/// Walk up from `dir` to the nearest `.nvmrc`, `.node-version` or `package.json` with
+/// `engines.node`, in that order within a folder.
+pub fn find_project_version(dir: &Path) -> AppResult<Option<ProjectVersion>> {
+    for dir in dir.ancestors() {
+        for name in [".nvmrc", ".node-version"] {
+            let file = dir.join(name);
+            if let Some(spec) = read_version_file(&file)? {
+                return Ok(Some(ProjectVersion {
+                    dir: dir.to_path_buf(),
+                    file,
+                    spec,
+                }));
+            }
+        }
+
+        let file = dir.join("package.json");
+        if let Some(spec) = read_engines(&file)? {
+            return Ok(Some(ProjectVersion {
+                dir: dir.to_path_buf(),
+                file,
+                spec,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The first line that isn't blank or a `#` comment, nvm allows comments after it too.
+fn read_version_file(file: &Path) -> AppResult<Option<String>> {
+    let content = match std::fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string))
+}
+
+/// `engines.node`, a package.json that isn't valid JSON is skipped like one without it.
+fn read_engines(file: &Path) -> AppResult<Option<String>> {
+    let content = match std::fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(serde_json::from_str::<serde_json::Value>(&content)
+        .ok()
+        .and_then(|package| {
+            package["engines"]["node"]
+                .as_str()
+                .map(|spec| spec.trim().to_string())
+        })
+        .filter(|spec| !spec.is_empty()))
+}
+
+#[test]
+fn test_find_project_version() {
+    let root = tempfile::tempdir().unwrap();
+    let app = root.path().join("app");
+    let src = app.join("src");
+    std::fs::create_dir_all(&src).unwrap();
+
+    assert_eq!(find_project_version(&src).unwrap(), None);
+
+    std::fs::write(
+        root.path().join(".nvmrc"),
+        "# pinned\n\nlts/hydrogen # for now\n",
+    )
+    .unwrap();
+    let found = find_project_version(&src).unwrap().unwrap();
+    assert_eq!(found.dir, root.path());
+    assert_eq!(found.spec, "lts/hydrogen");
+
+    // the nearest folder wins, whatever the file
+    std::fs::write(app.join("package.json"), "{ not json").unwrap();
+    assert_eq!(
+        find_project_version(&src).unwrap().unwrap().dir,
+        root.path()
+    );
+    std::fs::write(
+        app.join("package.json"),
+        r#"{"name":"app","engines":{"node":">=18 <21"}}"#,
+    )
+    .unwrap();
+    let found = find_project_version(&src).unwrap().unwrap();
+    assert_eq!(found.file, app.join("package.json"));
+    assert_eq!(found.spec, ">=18 <21");
+
+    // and within a folder .nvmrc and .node-version win over package.json
+    std::fs::write(app.join(".node-version"), "v20.11.1\n").unwrap();
+    let found = find_project_version(&src).unwrap().unwrap();
+    assert_eq!(found.file, app.join(".node-version"));
+    assert_eq!(found.spec, "v20.11.1");
+    std::fs::write(app.join(".nvmrc"), "18.19.0").unwrap();
+    assert_eq!(find_project_version(&app).unwrap().unwrap().spec, "18.19.0");
+}
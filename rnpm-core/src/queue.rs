@@ -0,0 +1,163 @@
+use std::{future::Future, sync::Mutex};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Runs at most `concurrency` downloads at a time, the others wait in FIFO order.
+pub struct DownloadQueue {
+    semaphore: Semaphore,
+    waiting: Mutex<Vec<String>>,
+    size: Mutex<Size>,
+}
+
+struct Size {
+    concurrency: usize,
+    /// Permits to drop instead of releasing, after shrinking while they were in use.
+    debt: usize,
+}
+
+impl DownloadQueue {
+    pub fn new(concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        Self {
+            semaphore: Semaphore::new(concurrency),
+            waiting: Mutex::new(vec![]),
+            size: Mutex::new(Size {
+                concurrency,
+                debt: 0,
+            }),
+        }
+    }
+
+    /// Change how many downloads run at once. Running downloads are never interrupted,
+    /// when shrinking the extra slots disappear as they finish.
+    pub fn set_concurrency(&self, concurrency: usize) {
+        let concurrency = concurrency.max(1);
+        let mut size = self.size.lock().unwrap();
+
+        if concurrency > size.concurrency {
+            let extra = concurrency - size.concurrency;
+            let repaid = extra.min(size.debt);
+            size.debt -= repaid;
+            self.semaphore.add_permits(extra - repaid);
+        } else {
+            let fewer = size.concurrency - concurrency;
+            size.debt += fewer - self.semaphore.forget_permits(fewer);
+        }
+
+        size.concurrency = concurrency;
+    }
+
+    /// Take a free download slot, or wait for one. Until the slot is granted the version is
+    /// listed in `waiting`, so the caller can report queue positions as soon as this returns.
+    pub fn acquire<'a>(&'a self, version: &str) -> impl Future<Output = QueuePermit<'a>> + 'a {
+        let permit = self.semaphore.try_acquire().ok();
+        let waiting = permit.is_none().then(|| Waiting::new(self, version));
+
+        async move {
+            let _waiting = waiting;
+            let permit = match permit {
+                Some(permit) => permit,
+                None => self
+                    .semaphore
+                    .acquire()
+                    .await
+                    .expect("download queue semaphore is never closed"),
+            };
+            QueuePermit {
+                queue: self,
+                permit: Some(permit),
+            }
+        }
+    }
+
+    /// The versions waiting for a slot, first in line first.
+    pub fn waiting(&self) -> Vec<String> {
+        self.waiting.lock().unwrap().clone()
+    }
+}
+
+/// A download slot, given back to the queue on drop.
+pub struct QueuePermit<'a> {
+    queue: &'a DownloadQueue,
+    permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for QueuePermit<'_> {
+    fn drop(&mut self) {
+        let mut size = self.queue.size.lock().unwrap();
+        if size.debt > 0 {
+            size.debt -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+struct Waiting<'a> {
+    queue: &'a DownloadQueue,
+    version: String,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(queue: &'a DownloadQueue, version: &str) -> Self {
+        queue.waiting.lock().unwrap().push(version.to_string());
+        Self {
+            queue,
+            version: version.to_string(),
+        }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.queue
+            .waiting
+            .lock()
+            .unwrap()
+            .retain(|version| version != &self.version);
+    }
+}
+
+#[tokio::test]
+async fn test_download_queue() {
+    let queue = DownloadQueue::new(1);
+    let first = queue.acquire("v20.11.1").await;
+    assert!(queue.waiting().is_empty());
+
+    {
+        let second = queue.acquire("v18.19.1");
+        assert_eq!(queue.waiting(), vec!["v18.19.1".to_string()]);
+
+        tokio::pin!(second);
+        tokio::select! {
+            biased;
+            _ = &mut second => panic!("the only slot is taken"),
+            _ = tokio::task::yield_now() => {}
+        }
+    }
+    assert!(queue.waiting().is_empty());
+
+    drop(first);
+    let _second = queue.acquire("v18.19.1").await;
+    assert!(queue.waiting().is_empty());
+}
+
+#[tokio::test]
+async fn test_download_queue_resize() {
+    let queue = DownloadQueue::new(2);
+    let first = queue.acquire("v20.11.1").await;
+    let second = queue.acquire("v18.19.1").await;
+
+    queue.set_concurrency(1);
+    drop(first);
+    assert!(queue.semaphore.try_acquire().is_err());
+
+    drop(second);
+    let third = queue.semaphore.try_acquire().unwrap();
+    assert!(queue.semaphore.try_acquire().is_err());
+    drop(third);
+
+    queue.set_concurrency(3);
+    assert_eq!(queue.semaphore.available_permits(), 3);
+}
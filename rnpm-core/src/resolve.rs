@@ -0,0 +1,255 @@
+use std::str::FromStr;
+
+use semver::{Version, VersionReq};
+
+use crate::{
+    error::{AppError, AppResult},
+    node::Node,
+};
+
+/// What can be asked for instead of an exact version.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Spec {
+    /// `latest`, `node`, `stable` or `current`: the newest version.
+    Latest,
+    /// `lts/*`: every LTS version.
+    Lts,
+    /// `lts/hydrogen`: one LTS line, by name.
+    LtsName(String),
+    /// `lts/-1`: the LTS line before the newest one.
+    LtsBack(usize),
+    /// `20.11.1` or `v20.11.1`.
+    Exact(Version),
+    /// `18`, `^18.17`, `>=16 <20` or `14 || 16`, like npm ranges.
+    Range(Vec<VersionReq>),
+}
+
+impl FromStr for Spec {
+    type Err = AppError;
+
+    fn from_str(spec: &str) -> AppResult<Self> {
+        let spec = spec.trim().to_lowercase();
+        let invalid = || AppError::new(format!("invalid version or range {spec}"));
+
+        if matches!(spec.as_str(), "latest" | "node" | "stable" | "current") {
+            return Ok(Self::Latest);
+        }
+        if let Some(line) = spec.strip_prefix("lts/") {
+            return match line {
+                "*" => Ok(Self::Lts),
+                _ if line.starts_with('-') => {
+                    Ok(Self::LtsBack(line[1..].parse().map_err(|_| invalid())?))
+                }
+                "" => Err(invalid()),
+                _ => Ok(Self::LtsName(line.to_string())),
+            };
+        }
+        if spec == "lts" {
+            return Ok(Self::Lts);
+        }
+        if let Ok(version) = Version::from_str(spec.trim_start_matches('v')) {
+            return Ok(Self::Exact(version));
+        }
+
+        spec.split("||")
+            .map(|range| range_req(range).ok_or_else(invalid))
+            .collect::<AppResult<Vec<_>>>()
+            .map(Self::Range)
+    }
+}
+
+/// npm separates comparators with spaces and allows `>= 16` and `v16`, semver wants `>=16, <20`.
+fn range_req(range: &str) -> Option<VersionReq> {
+    let mut comparators = vec![];
+    let mut op = String::new();
+    for token in range.split_whitespace() {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            op.push_str(token);
+            continue;
+        }
+        let version = token.trim_start_matches(|c| "<>=~^".contains(c));
+        let token_op = &token[..token.len() - version.len()];
+        let version = version.trim_start_matches('v');
+        if op.is_empty() && token_op.is_empty() {
+            comparators.push(bare_req(version));
+            continue;
+        }
+        comparators.push(format!("{op}{token_op}{version}"));
+        op.clear();
+    }
+
+    if comparators.is_empty() || !op.is_empty() {
+        return None;
+    }
+    VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+/// semver reads a version without an operator as `^`, npm as that version exactly:
+/// `18.17` is `18.17.*` and `1.2.3` is `=1.2.3`.
+fn bare_req(version: &str) -> String {
+    let parts = version.split('.').collect::<Vec<_>>();
+    if parts.iter().any(|part| ["x", "X", "*"].contains(part)) {
+        version.to_string()
+    } else if parts.len() < 3 {
+        format!("{version}.*")
+    } else {
+        format!("={version}")
+    }
+}
+
+impl Spec {
+    /// Whether this picks by LTS line, which needs `Node::lts` filled in.
+    pub fn needs_lts(&self) -> bool {
+        matches!(self, Self::Lts | Self::LtsName(_) | Self::LtsBack(_))
+    }
+
+    /// The nodes this matches, newest first.
+    pub fn matching<'a>(&self, nodes: &'a [Node]) -> Vec<&'a Node> {
+        let mut nodes = nodes
+            .iter()
+            .filter_map(|node| Some((parse(&node.version)?, node)))
+            .collect::<Vec<_>>();
+        nodes.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let lts_line = match self {
+            Self::LtsName(name) => Some(name.clone()),
+            Self::LtsBack(back) => {
+                let mut lines = vec![];
+                for lts in nodes.iter().filter_map(|(_, node)| node.lts.as_ref()) {
+                    let lts = lts.to_lowercase();
+                    if !lines.contains(&lts) {
+                        lines.push(lts);
+                    }
+                }
+                match lines.into_iter().nth(*back) {
+                    Some(line) => Some(line),
+                    None => return vec![],
+                }
+            }
+            _ => None,
+        };
+
+        let mut matching = nodes
+            .into_iter()
+            .filter(|(version, node)| match self {
+                Self::Latest => true,
+                Self::Lts => node.lts.is_some(),
+                Self::LtsName(_) | Self::LtsBack(_) => {
+                    node.lts.as_ref().map(|lts| lts.to_lowercase()) == lts_line
+                }
+                Self::Exact(exact) => version == exact,
+                Self::Range(reqs) => reqs.iter().any(|req| req.matches(version)),
+            })
+            .map(|(_, node)| node)
+            .collect::<Vec<_>>();
+
+        if *self == Self::Latest {
+            matching.truncate(1);
+        }
+        matching
+    }
+}
+
+/// The versions `spec` matches in `nodes`, newest first.
+pub fn resolve_all(spec: &str, nodes: &[Node]) -> AppResult<Vec<String>> {
+    Ok(Spec::from_str(spec)?
+        .matching(nodes)
+        .into_iter()
+        .map(|node| node.version.clone())
+        .collect())
+}
+
+/// The newest version `spec` matches in `nodes`.
+pub fn resolve(spec: &str, nodes: &[Node]) -> AppResult<String> {
+    resolve_all(spec, nodes)?
+        .into_iter()
+        .next()
+        .ok_or(AppError::new(format!("no version matches {}", spec.trim())))
+}
+
+fn parse(version: &str) -> Option<Version> {
+    Version::from_str(version.trim_start_matches('v')).ok()
+}
+
+#[test]
+fn test_spec_from_str() {
+    assert_eq!(Spec::from_str("latest").unwrap(), Spec::Latest);
+    assert_eq!(Spec::from_str("lts/*").unwrap(), Spec::Lts);
+    assert_eq!(
+        Spec::from_str("lts/Hydrogen").unwrap(),
+        Spec::LtsName("hydrogen".to_string())
+    );
+    assert_eq!(Spec::from_str("lts/-1").unwrap(), Spec::LtsBack(1));
+    assert_eq!(
+        Spec::from_str(" v20.11.1 ").unwrap(),
+        Spec::Exact(Version::new(20, 11, 1))
+    );
+    assert_eq!(
+        Spec::from_str(">= v16 <20").unwrap(),
+        Spec::Range(vec![VersionReq::parse(">=16, <20").unwrap()])
+    );
+    assert_eq!(
+        Spec::from_str("14 || ^16.1").unwrap(),
+        Spec::Range(vec![
+            VersionReq::parse("14.*").unwrap(),
+            VersionReq::parse("^16.1").unwrap()
+        ])
+    );
+
+    for (spec, lts) in [
+        ("lts/*", true),
+        ("lts/-1", true),
+        ("latest", false),
+        ("18", false),
+    ] {
+        assert_eq!(Spec::from_str(spec).unwrap().needs_lts(), lts, "{spec}");
+    }
+
+    for invalid in ["", "lts/", "lts/-x", "foo", ">=", "16 ||"] {
+        assert!(Spec::from_str(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_resolve() {
+    let nodes = [
+        ("v21.6.2", None),
+        ("v20.11.1", Some("Iron")),
+        ("v20.10.0", Some("Iron")),
+        ("v18.19.1", Some("Hydrogen")),
+        ("v18.17.0", Some("Hydrogen")),
+        ("v16.20.2", Some("Gallium")),
+        ("v15.14.0", None),
+    ]
+    .map(|(version, lts)| Node {
+        version: version.to_string(),
+        lts: lts.map(str::to_string),
+        files: vec![],
+        available: true,
+    });
+
+    let best = |spec| resolve(spec, &nodes).unwrap();
+    assert_eq!(best("latest"), "v21.6.2");
+    assert_eq!(best("lts/*"), "v20.11.1");
+    assert_eq!(best("lts/hydrogen"), "v18.19.1");
+    assert_eq!(best("lts/-1"), "v18.19.1");
+    assert_eq!(best("lts/-2"), "v16.20.2");
+    assert_eq!(best("18"), "v18.19.1");
+    assert_eq!(best("^18.17"), "v18.19.1");
+    assert_eq!(best("~18.17"), "v18.17.0");
+    assert_eq!(best(">=16 <20"), "v18.19.1");
+    assert_eq!(best("15 || 16"), "v16.20.2");
+    assert_eq!(best("20.10.0"), "v20.10.0");
+    assert_eq!(best("18.17"), "v18.17.0");
+    assert_eq!(best("16.20.2 || 18"), "v18.19.1");
+    assert_eq!(best("18.x"), "v18.19.1");
+    assert_eq!(best(">=18.17 18"), "v18.19.1");
+
+    assert_eq!(
+        resolve_all("lts/iron", &nodes).unwrap(),
+        ["v20.11.1", "v20.10.0"]
+    );
+    assert_eq!(resolve_all("latest", &nodes).unwrap(), ["v21.6.2"]);
+    assert!(resolve_all("lts/-3", &nodes).unwrap().is_empty());
+    assert!(resolve("19", &nodes).is_err());
+}
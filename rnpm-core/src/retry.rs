@@ -0,0 +1,145 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+
+use crate::error::{AppError, AppResult, ErrorKind};
+
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter: somewhere between half and all of
+    /// `base_delay * 2^(attempt - 1)`, capped at `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = exp / 2;
+        half + exp
+            .saturating_sub(half)
+            .mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+/// Run `f` until it succeeds, fails with a non transient error, or runs out of retries.
+///
+/// `on_retry` receives `(attempt, retries, error)` before waiting for the next attempt.
+pub async fn retry<T, F, Fut, R>(policy: &RetryPolicy, mut on_retry: R, mut f: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+    R: FnMut(u32, u32, &AppError) -> AppResult<()>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(e) if e.kind == ErrorKind::Network && attempt < policy.retries => {
+                attempt += 1;
+                on_retry(attempt, policy.retries, &e)?;
+                tokio::time::sleep(policy.delay(attempt)).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy {
+        retries: 5,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(1000),
+    };
+
+    for (attempt, max) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000)] {
+        let delay = policy.delay(attempt);
+        assert!(delay >= Duration::from_millis(max / 2));
+        assert!(delay <= Duration::from_millis(max));
+    }
+}
+
+/// Answers `503` to the first `failures` requests, then `status`.
+#[cfg(test)]
+async fn serve_unavailable(failures: usize, status: &'static str) -> String {
+    use crate::test_server::{serve, Response};
+
+    let addr = serve(move |n, _| {
+        let status = if n < failures {
+            "503 Service Unavailable"
+        } else {
+            status
+        };
+        Response::new(status, "ok")
+    })
+    .await;
+
+    format!("http://{addr}/index.json")
+}
+
+#[tokio::test]
+async fn test_retry_transient() {
+    let url = serve_unavailable(2, "200 OK").await;
+    let client = reqwest::Client::new();
+    let policy = RetryPolicy {
+        retries: 5,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    };
+
+    let mut attempts = vec![];
+    let text = retry(
+        &policy,
+        |attempt, retries, _| {
+            attempts.push((attempt, retries));
+            Ok(())
+        },
+        || crate::download::fetch_text(&client, &url),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(text, "ok");
+    assert_eq!(attempts, vec![(1, 5), (2, 5)]);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up() {
+    let client = reqwest::Client::new();
+    let policy = RetryPolicy {
+        retries: 2,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    };
+
+    let url = serve_unavailable(5, "200 OK").await;
+    let mut retries = 0;
+    let res = retry(
+        &policy,
+        |_, _, _| {
+            retries += 1;
+            Ok(())
+        },
+        || crate::download::fetch_text(&client, &url),
+    )
+    .await;
+    assert_eq!(res.unwrap_err().kind, ErrorKind::Network);
+    assert_eq!(retries, 2);
+
+    let url = serve_unavailable(0, "404 Not Found").await;
+    let mut retries = 0;
+    let res = retry(
+        &policy,
+        |_, _, _| {
+            retries += 1;
+            Ok(())
+        },
+        || crate::download::fetch_text(&client, &url),
+    )
+    .await;
+    assert_eq!(res.unwrap_err().kind, ErrorKind::Other);
+    assert_eq!(retries, 0);
+}
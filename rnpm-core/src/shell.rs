@@ -0,0 +1,487 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{AppError, AppResult},
+    node::{current_bin, current_version, link_current_version},
+};
+
+const BEGIN: &str = "# >>> rnpm >>>";
+const END: &str = "# <<< rnpm <<<";
+
+/// How the line older versions appended to `.bashrc` or `.zshenv` through globenv starts, only
+/// POSIX shells understand it.
+const LEGACY_PATH: &str = r#"export PATH=$HOME/.rnpm/$(cat "$HOME/.rnpm/version")/bin:"#;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl FromStr for Shell {
+    type Err = AppError;
+
+    fn from_str(name: &str) -> AppResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "powershell" | "pwsh" => Ok(Self::PowerShell),
+            _ => Err(AppError::new(format!(
+                "unknown shell {name}, pick bash, zsh, fish or powershell"
+            ))),
+        }
+    }
+}
+
+impl Shell {
+    pub const ALL: [Shell; 4] = [Self::Bash, Self::Zsh, Self::Fish, Self::PowerShell];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::PowerShell => "powershell",
+        }
+    }
+
+    /// The login shell from `SHELL`, PowerShell on Windows.
+    pub fn detect() -> Option<Self> {
+        if cfg!(windows) {
+            return Some(Self::PowerShell);
+        }
+        let shell = std::env::var_os("SHELL")?;
+        Self::from_str(Path::new(&shell).file_name()?.to_str()?).ok()
+    }
+
+    /// The startup file the integration goes in.
+    pub fn rc_file(&self, home: &Path) -> PathBuf {
+        match self {
+            Self::Bash => home.join(".bashrc"),
+            Self::Zsh => home.join(".zshrc"),
+            Self::Fish => home.join(".config/fish/config.fish"),
+            Self::PowerShell if cfg!(windows) => {
+                home.join(r"Documents\PowerShell\Microsoft.PowerShell_profile.ps1")
+            }
+            Self::PowerShell => home.join(".config/powershell/Microsoft.PowerShell_profile.ps1"),
+        }
+    }
+
+    /// Where older versions put `PATH` changes when that's not `rc_file`.
+    fn legacy_rc_file(&self, home: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Zsh => Some(home.join(".zshenv")),
+            _ => None,
+        }
+    }
+
+    /// Runs `rnpm env` whenever the current folder changes, and once at startup.
+    pub fn hook(&self, rnpm: &Path) -> String {
+        let rnpm = self.quote(&rnpm.to_string_lossy());
+        let name = self.name();
+        match self {
+            Self::Bash => format!(
+                r#"_rnpm_hook() {{
+  [ "$PWD" = "${{_RNPM_PWD:-}}" ] && return
+  _RNPM_PWD=$PWD
+  eval "$({rnpm} env --shell {name})"
+}}
+case ";${{PROMPT_COMMAND:-}};" in
+  *";_rnpm_hook;"*) ;;
+  *) PROMPT_COMMAND="_rnpm_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}" ;;
+esac
+_rnpm_hook"#
+            ),
+            Self::Zsh => format!(
+                r#"_rnpm_hook() {{
+  eval "$({rnpm} env --shell {name})"
+}}
+autoload -U add-zsh-hook
+add-zsh-hook chpwd _rnpm_hook
+_rnpm_hook"#
+            ),
+            Self::Fish => format!(
+                r#"function _rnpm_hook --on-variable PWD
+    {rnpm} env --shell {name} | source
+end
+_rnpm_hook"#
+            ),
+            Self::PowerShell => format!(
+                r#"function global:_rnpm_hook {{
+    & {rnpm} env --shell {name} | Out-String | Invoke-Expression
+}}
+if (-not $global:_rnpm_prompt) {{
+    $global:_rnpm_prompt = $function:prompt
+}}
+function global:prompt {{
+    if ($PWD.Path -ne $global:_rnpm_pwd) {{
+        $global:_rnpm_pwd = $PWD.Path
+        _rnpm_hook
+    }}
+    & $global:_rnpm_prompt
+}}"#
+            ),
+        }
+    }
+
+    /// The block `install` puts in `rc_file`: `current_bin` on `PATH` and, with the command line
+    /// around, the hook switching versions per folder.
+    pub fn snippet(&self, node_dir: &Path, rnpm: Option<&Path>) -> String {
+        let mut snippet = format!("{BEGIN}\n{}", self.prepend_path(&current_bin(node_dir)));
+        if let Some(rnpm) = rnpm {
+            snippet.push_str(&self.hook(rnpm));
+            snippet.push('\n');
+        }
+        snippet.push_str(END);
+        snippet.push('\n');
+        snippet
+    }
+
+    /// Code putting `dir` in front of `PATH`.
+    fn prepend_path(&self, dir: &Path) -> String {
+        let dir = self.quote(&dir.to_string_lossy());
+        match self {
+            Self::Bash | Self::Zsh => format!("export PATH={dir}:\"$PATH\"\n"),
+            Self::Fish => format!("set -gx PATH {dir} $PATH\n"),
+            Self::PowerShell => {
+                format!("$env:PATH = {dir} + [IO.Path]::PathSeparator + $env:PATH\n")
+            }
+        }
+    }
+
+    /// Code setting `PATH` to `paths`.
+    pub fn set_path(&self, paths: &[PathBuf]) -> AppResult<String> {
+        Ok(match self {
+            Self::Fish => {
+                let paths = paths
+                    .iter()
+                    .map(|path| self.quote(&path.to_string_lossy()))
+                    .collect::<Vec<_>>();
+                format!("set -gx PATH {};\n", paths.join(" "))
+            }
+            Self::PowerShell => {
+                let path = std::env::join_paths(paths)?;
+                format!("$env:PATH = {}\n", self.quote(&path.to_string_lossy()))
+            }
+            Self::Bash | Self::Zsh => {
+                let path = std::env::join_paths(paths)?;
+                format!("export PATH={};\n", self.quote(&path.to_string_lossy()))
+            }
+        })
+    }
+
+    /// Code setting the environment variable `name` to `value`, or removing it.
+    pub fn set_env(&self, name: &str, value: Option<&str>) -> String {
+        match (self, value) {
+            (Self::Bash | Self::Zsh, Some(value)) => {
+                format!("export {name}={};\n", self.quote(value))
+            }
+            (Self::Bash | Self::Zsh, None) => format!("unset {name};\n"),
+            (Self::Fish, Some(value)) => format!("set -gx {name} {};\n", self.quote(value)),
+            (Self::Fish, None) => format!("set -e {name};\n"),
+            (Self::PowerShell, Some(value)) => format!("$env:{name} = {}\n", self.quote(value)),
+            (Self::PowerShell, None) => {
+                format!("Remove-Item Env:{name} -ErrorAction SilentlyContinue\n")
+            }
+        }
+    }
+
+    fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            Self::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+            Self::PowerShell => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+/// `PATH` with `first` in front and whatever was there from `node_dir` before taken out.
+pub fn env_paths(node_dir: &Path, first: &[PathBuf], path: &OsStr) -> Vec<PathBuf> {
+    first
+        .iter()
+        .cloned()
+        .chain(std::env::split_paths(path).filter(|path| !path.starts_with(node_dir)))
+        .collect()
+}
+
+/// The `rnpm` command line the hooks run: next to `exe`, the running program, or on `PATH`.
+pub fn find_rnpm(exe: &Path) -> Option<PathBuf> {
+    let name = format!("rnpm{}", std::env::consts::EXE_SUFFIX);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    exe.parent()
+        .into_iter()
+        .map(Path::to_path_buf)
+        .chain(std::env::split_paths(&path))
+        .map(|dir| dir.join(&name))
+        .find(|rnpm| rnpm.is_file())
+}
+
+/// What `install` or `remove` would do to one shell.
+#[derive(Serialize, Debug)]
+pub struct ShellSetup {
+    pub shell: Shell,
+    pub rc_file: PathBuf,
+    pub snippet: String,
+    /// Whether `rc_file` has the integration already.
+    pub installed: bool,
+    /// Whether `rc_file` or `legacy_rc_file` has the line older versions wrote, which both take
+    /// out.
+    pub legacy: bool,
+    /// The other file both change, only set when it has that line.
+    pub legacy_rc_file: Option<PathBuf>,
+}
+
+pub fn plan(
+    home: &Path,
+    node_dir: &Path,
+    rnpm: Option<&Path>,
+    shell: Shell,
+) -> AppResult<ShellSetup> {
+    let rc_file = shell.rc_file(home);
+    let content = read(&rc_file)?;
+    let legacy_rc_file = match shell.legacy_rc_file(home) {
+        Some(path) => {
+            let content = read(&path)?;
+            (strip(&content) != content).then_some(path)
+        }
+        None => None,
+    };
+
+    Ok(ShellSetup {
+        shell,
+        snippet: shell.snippet(node_dir, rnpm),
+        installed: content.contains(BEGIN),
+        legacy: legacy_rc_file.is_some() || content.lines().any(is_legacy),
+        legacy_rc_file,
+        rc_file,
+    })
+}
+
+/// Add the integration to the rc file of `shell`, or update it in place, and create
+/// `current_link` for a version picked before there was one.
+pub fn install(
+    home: &Path,
+    node_dir: &Path,
+    rnpm: Option<&Path>,
+    shell: Shell,
+) -> AppResult<PathBuf> {
+    let rc_file = shell.rc_file(home);
+    let mut content = strip(&read(&rc_file)?);
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&shell.snippet(node_dir, rnpm));
+
+    if let Some(dir) = rc_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&rc_file, content)?;
+    remove_legacy(home, shell)?;
+
+    if let Some(version) = current_version(node_dir)? {
+        link_current_version(node_dir, &version)?;
+    }
+    Ok(rc_file)
+}
+
+/// Take the integration out of the rc file of `shell`, `false` when it wasn't there.
+pub fn remove(home: &Path, shell: Shell) -> AppResult<bool> {
+    let rc_file = shell.rc_file(home);
+    let content = read(&rc_file)?;
+    let stripped = strip(&content);
+    let removed = stripped != content;
+    if removed {
+        std::fs::write(&rc_file, stripped)?;
+    }
+    Ok(remove_legacy(home, shell)? || removed)
+}
+
+fn remove_legacy(home: &Path, shell: Shell) -> AppResult<bool> {
+    let Some(legacy_rc_file) = shell.legacy_rc_file(home) else {
+        return Ok(false);
+    };
+    let content = read(&legacy_rc_file)?;
+    let stripped = strip(&content);
+    if stripped == content {
+        return Ok(false);
+    }
+    std::fs::write(&legacy_rc_file, stripped)?;
+    Ok(true)
+}
+
+fn read(rc_file: &Path) -> AppResult<String> {
+    match std::fs::read_to_string(rc_file) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn is_legacy(line: &str) -> bool {
+    line.trim().starts_with(LEGACY_PATH)
+}
+
+/// `content` without the rnpm block and the legacy `PATH` line, everything else untouched.
+fn strip(content: &str) -> String {
+    let mut res = String::new();
+    let mut inside = false;
+    for line in content.split_inclusive('\n') {
+        match line.trim() {
+            BEGIN => inside = true,
+            END if inside => inside = false,
+            _ if inside || is_legacy(line) => {}
+            _ => res.push_str(line),
+        }
+    }
+    res
+}
+
+#[test]
+fn test_install_and_remove() {
+    let home = tempfile::tempdir().unwrap();
+    let node_dir = home.path().join(".rnpm");
+    let rnpm = Some(Path::new("/opt/rnpm/bin/rnpm"));
+    let bashrc = Shell::Bash.rc_file(home.path());
+    let before = format!("alias ll='ls -l'\n{LEGACY_PATH}$PATH:$PATH\nexport EDITOR=vim");
+    std::fs::write(&bashrc, &before).unwrap();
+
+    let setup = plan(home.path(), &node_dir, rnpm, Shell::Bash).unwrap();
+    assert!(!setup.installed && setup.legacy);
+    assert!(setup
+        .snippet
+        .contains("'/opt/rnpm/bin/rnpm' env --shell bash"));
+    let current = current_bin(&node_dir).to_string_lossy().into_owned();
+    assert!(setup
+        .snippet
+        .contains(&format!("export PATH='{current}':\"$PATH\"\n")));
+
+    // without the command line only `PATH` is set
+    let snippet = Shell::Fish.snippet(&node_dir, None);
+    assert_eq!(
+        snippet,
+        format!("{BEGIN}\nset -gx PATH '{current}' $PATH\n{END}\n")
+    );
+
+    install(home.path(), &node_dir, rnpm, Shell::Bash).unwrap();
+    install(home.path(), &node_dir, rnpm, Shell::Bash).unwrap();
+    let content = std::fs::read_to_string(&bashrc).unwrap();
+    assert_eq!(content.matches(BEGIN).count(), 1);
+    assert!(content.starts_with("alias ll='ls -l'\nexport EDITOR=vim\n# >>> rnpm >>>\n"));
+    assert!(
+        plan(home.path(), &node_dir, rnpm, Shell::Bash)
+            .unwrap()
+            .installed
+    );
+
+    // fish has no config yet
+    let fish = install(home.path(), &node_dir, rnpm, Shell::Fish).unwrap();
+    assert!(std::fs::read_to_string(fish)
+        .unwrap()
+        .contains("--on-variable PWD"));
+
+    // sourcing the profile twice mustn't wrap our own prompt
+    assert!(Shell::PowerShell.hook(Path::new("rnpm")).contains(
+        "if (-not $global:_rnpm_prompt) {\n    $global:_rnpm_prompt = $function:prompt\n}"
+    ));
+
+    assert!(remove(home.path(), Shell::Bash).unwrap());
+    assert!(!remove(home.path(), Shell::Bash).unwrap());
+    assert_eq!(
+        std::fs::read_to_string(&bashrc).unwrap(),
+        "alias ll='ls -l'\nexport EDITOR=vim\n"
+    );
+    assert!(remove(home.path(), Shell::Fish).unwrap());
+
+    // zsh had it in .zshenv
+    let zshenv = home.path().join(".zshenv");
+    std::fs::write(&zshenv, format!("{LEGACY_PATH}$PATH:$PATH\n")).unwrap();
+    assert!(
+        plan(home.path(), &node_dir, rnpm, Shell::Zsh)
+            .unwrap()
+            .legacy
+    );
+    install(home.path(), &node_dir, rnpm, Shell::Zsh).unwrap();
+    assert_eq!(std::fs::read_to_string(&zshenv).unwrap(), "");
+    assert_eq!(
+        plan(home.path(), &node_dir, rnpm, Shell::Zsh)
+            .unwrap()
+            .legacy_rc_file,
+        None
+    );
+    assert!(
+        plan(home.path(), &node_dir, rnpm, Shell::Zsh)
+            .unwrap()
+            .installed
+    );
+
+    // a version picked before `current` existed gets it on install
+    #[cfg(unix)]
+    {
+        std::fs::create_dir_all(node_dir.join("v20.11.1")).unwrap();
+        std::fs::write(node_dir.join("version"), "v20.11.1").unwrap();
+        install(home.path(), &node_dir, rnpm, Shell::Zsh).unwrap();
+        assert_eq!(
+            std::fs::read_link(crate::node::current_link(&node_dir)).unwrap(),
+            Path::new("v20.11.1")
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_set_path() {
+    let node_dir = Path::new("/home/o'neil/.rnpm");
+    let paths = env_paths(
+        node_dir,
+        &[node_dir.join("shims"), node_dir.join("v20.11.1/bin")],
+        OsStr::new("/home/o'neil/.rnpm/v18.19.1/bin:/usr/bin:/home/o'neil/.rnpm/shims:/bin"),
+    );
+
+    assert_eq!(
+        Shell::Bash.set_path(&paths).unwrap(),
+        "export PATH='/home/o'\\''neil/.rnpm/shims:/home/o'\\''neil/.rnpm/v20.11.1/bin:/usr/bin:/bin';\n"
+    );
+    assert_eq!(
+        Shell::Fish.set_path(&paths).unwrap(),
+        "set -gx PATH '/home/o\\'neil/.rnpm/shims' '/home/o\\'neil/.rnpm/v20.11.1/bin' '/usr/bin' '/bin';\n"
+    );
+    assert_eq!(
+        Shell::PowerShell.set_path(&paths).unwrap(),
+        "$env:PATH = '/home/o''neil/.rnpm/shims:/home/o''neil/.rnpm/v20.11.1/bin:/usr/bin:/bin'\n"
+    );
+}
+
+#[test]
+fn test_set_env() {
+    let name = "RNPM_NODE_VERSION";
+    assert_eq!(
+        Shell::Zsh.set_env(name, Some("v18.19.1")),
+        "export RNPM_NODE_VERSION='v18.19.1';\n"
+    );
+    assert_eq!(
+        Shell::Bash.set_env(name, None),
+        "unset RNPM_NODE_VERSION;\n"
+    );
+    assert_eq!(
+        Shell::Fish.set_env(name, Some("v18.19.1")),
+        "set -gx RNPM_NODE_VERSION 'v18.19.1';\n"
+    );
+    assert_eq!(
+        Shell::Fish.set_env(name, None),
+        "set -e RNPM_NODE_VERSION;\n"
+    );
+    assert_eq!(
+        Shell::PowerShell.set_env(name, Some("v18.19.1")),
+        "$env:RNPM_NODE_VERSION = 'v18.19.1'\n"
+    );
+}
@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::AppResult;
+
+/// The commands that get a shim, each running the same command of the active version.
+pub const SHIMS: [&str; 4] = ["node", "npm", "npx", "corepack"];
+
+/// The one folder to put on `PATH`, whatever version is active.
+pub fn shims_dir(node_dir: &Path) -> PathBuf {
+    node_dir.join("shims")
+}
+
+/// Which shim `argv0` is, the file name it was started as.
+pub fn shim_name(argv0: &Path) -> Option<&'static str> {
+    let stem = argv0.file_stem()?.to_str()?.to_lowercase();
+    SHIMS.into_iter().find(|name| *name == stem)
+}
+
+/// Put a link to `shim`, the `rnpm-shim` executable, in `shims_dir` for each of `SHIMS`,
+/// replacing older ones. Windows gets copies, links there need extra privileges.
+pub fn install_shims(node_dir: &Path, shim: &Path) -> AppResult<PathBuf> {
+    let dir = shims_dir(node_dir);
+    std::fs::create_dir_all(&dir)?;
+
+    for name in SHIMS {
+        let path = dir.join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(shim, &path)?;
+
+        #[cfg(windows)]
+        std::fs::copy(shim, &path)?;
+    }
+
+    Ok(dir)
+}
+
+#[test]
+fn test_shim_name() {
+    assert_eq!(
+        shim_name(Path::new("/home/me/.rnpm/shims/node")),
+        Some("node")
+    );
+    assert_eq!(shim_name(Path::new("npx")), Some("npx"));
+    assert_eq!(shim_name(Path::new("NPM.exe")), Some("npm"));
+    assert_eq!(shim_name(Path::new("/usr/bin/rnpm-shim")), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_install_shims() {
+    let home = tempfile::tempdir().unwrap();
+    let shim = home.path().join("rnpm-shim");
+    std::fs::write(&shim, "").unwrap();
+    std::fs::create_dir_all(shims_dir(home.path())).unwrap();
+    std::fs::write(shims_dir(home.path()).join("node"), "stale").unwrap();
+
+    let dir = install_shims(home.path(), &shim).unwrap();
+    for name in SHIMS {
+        assert_eq!(std::fs::read_link(dir.join(name)).unwrap(), shim);
+    }
+    // again, over the links
+    install_shims(home.path(), &shim).unwrap();
+}
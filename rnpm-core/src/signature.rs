@@ -0,0 +1,208 @@
+use std::path::Path;
+
+use pgp::{
+    cleartext::CleartextSignedMessage, Deserializable, SignedPublicKey, StandaloneSignature,
+};
+use reqwest::Client;
+
+use crate::{
+    download::{fetch_bytes, fetch_text},
+    error::{AppError, AppResult, ErrorKind},
+};
+
+/// Load every public key stored in `dir`, armored or binary, one or more keys per file.
+pub fn load_keyring(dir: &Path) -> AppResult<Vec<SignedPublicKey>> {
+    let mut keys = vec![];
+
+    if dir.is_dir() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let content = std::fs::read(&path)?;
+            let parsed = match std::str::from_utf8(&content) {
+                Ok(text) if text.trim_start().starts_with("-----BEGIN") => {
+                    SignedPublicKey::from_string_many(text)?
+                        .0
+                        .collect::<Result<Vec<_>, _>>()
+                }
+                _ => SignedPublicKey::from_bytes_many(&content[..]).collect::<Result<Vec<_>, _>>(),
+            };
+
+            keys.extend(parsed.map_err(|e| {
+                AppError::with_kind(
+                    ErrorKind::Signature,
+                    format!("invalid release key {}: {e}", path.to_string_lossy()),
+                )
+            })?);
+        }
+    }
+
+    if keys.is_empty() {
+        return Err(AppError::with_kind(
+            ErrorKind::Signature,
+            format!("no release keys found in {}", dir.to_string_lossy()),
+        ));
+    }
+
+    Ok(keys)
+}
+
+/// Verify a detached `SHASUMS256.txt.sig` signature over `data`.
+pub fn verify_detached(keys: &[SignedPublicKey], data: &[u8], signature: &[u8]) -> AppResult<()> {
+    let signature = match std::str::from_utf8(signature) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN") => {
+            StandaloneSignature::from_string(text).map(|(sig, _)| sig)
+        }
+        _ => StandaloneSignature::from_bytes(signature),
+    }
+    .map_err(|e| AppError::with_kind(ErrorKind::Signature, format!("invalid signature: {e}")))?;
+
+    let verified = keys.iter().any(|key| {
+        signature.verify(key, data).is_ok()
+            || key
+                .public_subkeys
+                .iter()
+                .any(|subkey| signature.verify(subkey, data).is_ok())
+    });
+
+    if verified {
+        Ok(())
+    } else {
+        Err(AppError::with_kind(
+            ErrorKind::Signature,
+            "SHASUMS256.txt signature doesn't match any trusted release key",
+        ))
+    }
+}
+
+/// Verify a clearsigned `SHASUMS256.txt.asc` and return the signed text.
+pub fn verify_cleartext(keys: &[SignedPublicKey], message: &str) -> AppResult<String> {
+    let (message, _) = CleartextSignedMessage::from_string(message).map_err(|e| {
+        AppError::with_kind(ErrorKind::Signature, format!("invalid signed message: {e}"))
+    })?;
+
+    let verified = keys.iter().any(|key| {
+        message.verify(key).is_ok()
+            || key
+                .public_subkeys
+                .iter()
+                .any(|subkey| message.verify(subkey).is_ok())
+    });
+
+    if verified {
+        Ok(message.signed_text())
+    } else {
+        Err(AppError::with_kind(
+            ErrorKind::Signature,
+            "SHASUMS256.txt.asc signature doesn't match any trusted release key",
+        ))
+    }
+}
+
+/// Fetch the `SHASUMS256.txt` at `url` and only return it once its signature is trusted,
+/// falling back to the clearsigned `.asc` when no detached `.sig` is published.
+pub async fn fetch_verified_shasums(
+    client: &Client,
+    url: &str,
+    keyring_dir: &Path,
+) -> AppResult<String> {
+    let keys = load_keyring(keyring_dir)?;
+
+    match fetch_bytes(client, &format!("{url}.sig")).await {
+        Ok(sig) => {
+            let shasums = fetch_text(client, url).await?;
+            verify_detached(&keys, shasums.as_bytes(), &sig)?;
+            Ok(shasums)
+        }
+        Err(e) if e.kind == ErrorKind::Network => Err(e),
+        Err(_) => {
+            let asc = fetch_text(client, &format!("{url}.asc")).await?;
+            verify_cleartext(&keys, &asc)
+        }
+    }
+}
+
+#[cfg(test)]
+fn fixture_keyring() -> Vec<SignedPublicKey> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/signature/keys");
+    load_keyring(&dir).unwrap()
+}
+
+#[test]
+fn test_verify_detached() {
+    let keys = fixture_keyring();
+    let data = include_bytes!("../fixtures/signature/SHASUMS256.txt");
+    let sig = include_bytes!("../fixtures/signature/SHASUMS256.txt.sig");
+
+    assert!(verify_detached(&keys, data, sig).is_ok());
+
+    let mut tampered = data.to_vec();
+    tampered[0] = b'0';
+    let err = verify_detached(&keys, &tampered, sig).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Signature);
+}
+
+#[test]
+fn test_verify_cleartext() {
+    let keys = fixture_keyring();
+    let message = include_str!("../fixtures/signature/SHASUMS256.txt.asc");
+
+    let text = verify_cleartext(&keys, message).unwrap();
+    assert!(text.contains("node-v20.11.1-linux-x64.tar.xz"));
+
+    let tampered = message.replace("ba7816bf", "00000000");
+    let err = verify_cleartext(&keys, &tampered).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Signature);
+}
+
+#[test]
+fn test_load_keyring_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = load_keyring(dir.path()).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Signature);
+}
+
+#[tokio::test]
+async fn test_fetch_verified_shasums_from_local_mirror() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/signature");
+    let keyring_dir = fixtures.join("keys");
+    let client = Client::new();
+
+    let url = reqwest::Url::from_file_path(fixtures.join("SHASUMS256.txt")).unwrap();
+    let shasums = fetch_verified_shasums(&client, url.as_str(), &keyring_dir)
+        .await
+        .unwrap();
+    assert!(shasums.contains("node-v20.11.1-linux-x64.tar.xz"));
+
+    // a mirror only publishing the clearsigned SHASUMS256.txt.asc
+    let mirror = tempfile::tempdir().unwrap();
+    std::fs::copy(
+        fixtures.join("SHASUMS256.txt.asc"),
+        mirror.path().join("SHASUMS256.txt.asc"),
+    )
+    .unwrap();
+    let url = reqwest::Url::from_file_path(mirror.path().join("SHASUMS256.txt")).unwrap();
+    let shasums = fetch_verified_shasums(&client, url.as_str(), &keyring_dir)
+        .await
+        .unwrap();
+    assert!(shasums.contains("node-v20.11.1-linux-x64.tar.xz"));
+
+    // a tampered SHASUMS256.txt next to a valid signature
+    std::fs::copy(
+        fixtures.join("SHASUMS256.txt.sig"),
+        mirror.path().join("SHASUMS256.txt.sig"),
+    )
+    .unwrap();
+    std::fs::write(
+        mirror.path().join("SHASUMS256.txt"),
+        "0000  node-v20.11.1-linux-x64.tar.xz\n",
+    )
+    .unwrap();
+    let err = fetch_verified_shasums(&client, url.as_str(), &keyring_dir)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::Signature);
+}
@@ -0,0 +1,73 @@
+//! A tiny HTTP server for tests, answering each request the way a script says.
+
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// What to answer one request with.
+pub struct Response {
+    status: &'static str,
+    headers: Vec<String>,
+    body: Vec<u8>,
+    cut: Option<usize>,
+}
+
+impl Response {
+    pub fn new(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+            cut: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.headers.push(format!("{name}: {value}"));
+        self
+    }
+
+    /// Hang up after `len` bytes of the body, `Content-Length` still announcing all of it.
+    pub fn cut(mut self, len: usize) -> Self {
+        self.cut = Some(len);
+        self
+    }
+}
+
+/// Serve on a free local port, answering request `n` (counting from 0) and its raw head with
+/// whatever `script` returns. One request per connection.
+pub async fn serve<F>(mut script: F) -> SocketAddr
+where
+    F: FnMut(usize, &str) -> Response + Send + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        for n in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let len = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..len]);
+
+            let response = script(n, &request);
+            let mut head = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+                response.status,
+                response.body.len()
+            );
+            for header in &response.headers {
+                head.push_str(header);
+                head.push_str("\r\n");
+            }
+            head.push_str("\r\n");
+            let body = &response.body[..response.cut.unwrap_or(response.body.len())];
+
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(body).await.unwrap();
+            socket.shutdown().await.unwrap();
+        }
+    });
+
+    addr
+}
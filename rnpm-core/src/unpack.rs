@@ -0,0 +1,270 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    TarXz,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Self::TarXz => "tar.xz",
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+
+    /// The kind of `file_name` and the name left without its suffix.
+    pub fn from_file_name(file_name: &str) -> Option<(Self, &str)> {
+        [Self::TarXz, Self::TarGz, Self::Zip]
+            .into_iter()
+            .find_map(|kind| {
+                let name = file_name.strip_suffix(kind.suffix())?.strip_suffix('.')?;
+                Some((kind, name))
+            })
+    }
+}
+
+/// Archives are extracted into `.staging-{version}` next to the installed versions first,
+/// so a failed or interrupted install never leaves a folder that looks like a version.
+const STAGING_PREFIX: &str = ".staging-";
+
+/// Extract a node archive into a staging dir, check it holds a node binary, then rename its
+/// top-level `node-vX-platform-arch` folder to `target_path/version` in one step. An existing
+/// install is only moved aside once the new one is complete, and back if the rename fails.
+pub fn unpack(version: String, file_path: PathBuf, target_path: PathBuf) -> AppResult<()> {
+    let staging = target_path.join(format!("{STAGING_PREFIX}{version}"));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+
+    let res = extract(&file_path, &staging).and_then(|dir| {
+        let target = target_path.join(&version);
+        if !target.exists() {
+            std::fs::rename(dir, target)?;
+            return Ok(());
+        }
+
+        let old = staging.join("old");
+        std::fs::rename(&target, &old)?;
+        if let Err(e) = std::fs::rename(dir, &target) {
+            let _ = std::fs::rename(&old, &target);
+            return Err(e.into());
+        }
+        Ok(())
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    res
+}
+
+/// Remove what interrupted installs left behind.
+pub fn clean_staging(target_path: &Path) -> AppResult<()> {
+    let entries = match std::fs::read_dir(target_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(STAGING_PREFIX)
+        {
+            std::fs::remove_dir_all(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Extract `file_path` into `staging` and return the folder holding the node install.
+fn extract(file_path: &Path, staging: &Path) -> AppResult<PathBuf> {
+    let file = std::fs::File::open(file_path)?;
+    let file_name = file_path
+        .file_name()
+        .ok_or(AppError::new(format!(
+            "can't get filename from path:{}",
+            file_path.to_string_lossy()
+        )))?
+        .to_string_lossy()
+        .to_string();
+
+    let (kind, dir_name) = ArchiveKind::from_file_name(&file_name)
+        .ok_or(AppError::new(format!("unsupported archive:{file_name}")))?;
+    let binary = match kind {
+        ArchiveKind::TarXz => {
+            let xz = xz2::read::XzDecoder::new(file);
+            tar::Archive::new(xz).unpack(staging)?;
+            "bin/node"
+        }
+        ArchiveKind::TarGz => {
+            let gz = flate2::read::GzDecoder::new(file);
+            tar::Archive::new(gz).unpack(staging)?;
+            "bin/node"
+        }
+        ArchiveKind::Zip => {
+            unpack_zip(file, staging)?;
+            "node.exe"
+        }
+    };
+
+    let dir = staging.join(dir_name);
+    if !dir.join(binary).is_file() {
+        return Err(AppError::new(format!("{file_name} has no {binary}")));
+    }
+    Ok(dir)
+}
+
+/// Entries escaping `target_path` are rejected by `ZipArchive::extract`.
+fn unpack_zip(file: std::fs::File, target_path: &Path) -> AppResult<()> {
+    let mut zip = zip::ZipArchive::new(std::io::BufReader::new(file))?;
+    zip.extract(target_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+fn unpack_fixture(file_name: &str, version: &str) -> tempfile::TempDir {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/unpack");
+    let target = tempfile::tempdir().unwrap();
+    unpack(
+        version.to_string(),
+        fixtures.join(file_name),
+        target.path().to_path_buf(),
+    )
+    .unwrap();
+    target
+}
+
+#[test]
+fn test_unpack_tar_xz() {
+    let target = unpack_fixture("node-v20.11.1-linux-x64.tar.xz", "v20.11.1");
+
+    assert!(target.path().join("v20.11.1/bin/node").is_file());
+    assert!(!target.path().join("node-v20.11.1-linux-x64").exists());
+}
+
+#[test]
+fn test_unpack_tar_gz() {
+    let target = unpack_fixture("node-v0.12.18-linux-x64.tar.gz", "v0.12.18");
+
+    assert!(target.path().join("v0.12.18/bin/node").is_file());
+    assert!(!target.path().join("node-v0.12.18-linux-x64").exists());
+}
+
+#[test]
+fn test_unpack_zip() {
+    let target = unpack_fixture("node-v20.11.1-win-x64.zip", "v20.11.1");
+
+    assert_eq!(
+        std::fs::read_to_string(target.path().join("v20.11.1/node.exe")).unwrap(),
+        "not really node\n"
+    );
+    assert!(target
+        .path()
+        .join("v20.11.1/node_modules/npm/package.json")
+        .is_file());
+    assert!(!target.path().join("node-v20.11.1-win-x64").exists());
+}
+
+#[test]
+fn test_unpack_replaces_install() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/unpack/node-v20.11.1-linux-x64.tar.xz");
+    let target = unpack_fixture("node-v20.11.1-linux-x64.tar.xz", "v20.11.1");
+    let install = target.path().join("v20.11.1");
+    std::fs::remove_file(install.join("bin/node")).unwrap();
+    std::fs::write(install.join("leftover"), "").unwrap();
+
+    // a broken archive keeps the install there is
+    let archive = target.path().join("node-v20.11.1-linux-x64.tar.xz");
+    std::fs::write(&archive, "not an archive").unwrap();
+    assert!(unpack("v20.11.1".to_string(), archive, target.path().to_path_buf()).is_err());
+    assert!(install.join("leftover").is_file());
+
+    unpack("v20.11.1".to_string(), fixture, target.path().to_path_buf()).unwrap();
+    assert!(install.join("bin/node").is_file());
+    assert!(!install.join("leftover").exists());
+    let mut names = std::fs::read_dir(target.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["node-v20.11.1-linux-x64.tar.xz", "v20.11.1"]);
+}
+
+#[test]
+fn test_archive_kind_from_file_name() {
+    assert_eq!(
+        ArchiveKind::from_file_name("node-v20.11.1-linux-x64.tar.xz"),
+        Some((ArchiveKind::TarXz, "node-v20.11.1-linux-x64"))
+    );
+    assert_eq!(
+        ArchiveKind::from_file_name("node-v0.12.18-darwin-x64.tar.gz"),
+        Some((ArchiveKind::TarGz, "node-v0.12.18-darwin-x64"))
+    );
+    assert_eq!(
+        ArchiveKind::from_file_name("node-v20.11.1-win-x64.zip"),
+        Some((ArchiveKind::Zip, "node-v20.11.1-win-x64"))
+    );
+    assert_eq!(ArchiveKind::from_file_name("node-v20.11.1-x64.msi"), None);
+}
+
+#[test]
+fn test_unpack_leaves_nothing_behind_on_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("node");
+
+    // an archive without bin/node
+    let archive = dir.path().join("node-v20.11.1-linux-x64.tar.gz");
+    let gz = flate2::write::GzEncoder::new(
+        std::fs::File::create(&archive).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut tar = tar::Builder::new(gz);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(3);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(
+        &mut header,
+        "node-v20.11.1-linux-x64/README.md",
+        &b"hi\n"[..],
+    )
+    .unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+
+    let err = unpack("v20.11.1".to_string(), archive, target.clone()).unwrap_err();
+    assert!(err.message.contains("bin/node"));
+
+    // a truncated download
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/unpack/node-v20.11.1-linux-x64.tar.xz");
+    let bytes = std::fs::read(fixture).unwrap();
+    let archive = dir.path().join("node-v20.11.1-linux-x64.tar.xz");
+    std::fs::write(&archive, &bytes[..bytes.len() / 2]).unwrap();
+    assert!(unpack("v20.11.1".to_string(), archive, target.clone()).is_err());
+
+    assert_eq!(std::fs::read_dir(&target).unwrap().count(), 0);
+}
+
+#[test]
+fn test_clean_staging() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(
+        dir.path()
+            .join(".staging-v20.11.1/node-v20.11.1-linux-x64/bin"),
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("v18.19.1/bin")).unwrap();
+
+    clean_staging(dir.path()).unwrap();
+
+    assert!(!dir.path().join(".staging-v20.11.1").exists());
+    assert!(dir.path().join("v18.19.1/bin").exists());
+    assert!(clean_staging(&dir.path().join("missing")).is_ok());
+}
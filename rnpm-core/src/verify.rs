@@ -0,0 +1,111 @@
+use std::{path::Path, process::Command, str::FromStr};
+
+use semver::Version;
+use serde::Serialize;
+
+use crate::node::binary_path;
+
+/// What `verify_install` found wrong with an installed version, nothing when it's healthy.
+#[derive(Serialize, Clone, Debug)]
+pub struct VerifyReport {
+    pub version: String,
+    pub ok: bool,
+    pub problems: Vec<String>,
+}
+
+/// Check `dir` holds the binaries `version` ships with and that its `node` runs and
+/// reports `version`. Blocking, it spawns `node --version`.
+pub fn verify_install(dir: &Path, version: &str) -> VerifyReport {
+    let mut problems = vec![];
+
+    for name in expected_binaries(version) {
+        if !binary_path(dir, name).is_file() {
+            problems.push(format!("{name} is missing"));
+        }
+    }
+
+    let node = binary_path(dir, "node");
+    if node.is_file() {
+        match Command::new(&node).arg("--version").output() {
+            Ok(output) if output.status.success() => {
+                let reported = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if reported != version {
+                    problems.push(format!("node --version reports {reported}"));
+                }
+            }
+            Ok(output) => problems.push(format!("node --version failed: {}", output.status)),
+            Err(e) => problems.push(format!("node can't run: {e}")),
+        }
+    }
+
+    VerifyReport {
+        version: version.to_string(),
+        ok: problems.is_empty(),
+        problems,
+    }
+}
+
+/// `npx` came with npm 5.2 in node 8.2, `corepack` with node 16.9 and 14.19.
+fn expected_binaries(version: &str) -> Vec<&'static str> {
+    let mut names = vec!["node", "npm"];
+    let Ok(version) = Version::from_str(version.trim_start_matches('v')) else {
+        return names;
+    };
+
+    if version >= Version::new(8, 2, 0) {
+        names.push("npx");
+    }
+    if version >= Version::new(16, 9, 0)
+        || (version >= Version::new(14, 19, 0) && version.major == 14)
+    {
+        names.push("corepack");
+    }
+    names
+}
+
+#[test]
+fn test_expected_binaries() {
+    assert_eq!(expected_binaries("v0.12.18"), ["node", "npm"]);
+    assert_eq!(expected_binaries("v8.17.0"), ["node", "npm", "npx"]);
+    assert_eq!(expected_binaries("v14.18.3"), ["node", "npm", "npx"]);
+    assert_eq!(
+        expected_binaries("v14.21.3"),
+        ["node", "npm", "npx", "corepack"]
+    );
+    assert_eq!(expected_binaries("v15.14.0"), ["node", "npm", "npx"]);
+    assert_eq!(
+        expected_binaries("v20.11.1"),
+        ["node", "npm", "npx", "corepack"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_verify_install() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let bin = dir.path().join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let write_node = |version: &str| {
+        let node = bin.join("node");
+        std::fs::write(&node, format!("#!/bin/sh\necho {version}\n")).unwrap();
+        std::fs::set_permissions(&node, std::fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    write_node("v20.11.1");
+    for name in ["npm", "npx", "corepack"] {
+        std::fs::write(bin.join(name), "").unwrap();
+    }
+
+    let report = verify_install(dir.path(), "v20.11.1");
+    assert!(report.ok, "{:?}", report.problems);
+
+    write_node("v18.19.1");
+    std::fs::remove_file(bin.join("npx")).unwrap();
+    let report = verify_install(dir.path(), "v20.11.1");
+    assert!(!report.ok);
+    assert_eq!(
+        report.problems,
+        ["npx is missing", "node --version reports v18.19.1"]
+    );
+}
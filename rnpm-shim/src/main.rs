@@ -0,0 +1,72 @@
+//! Installed as `node`, `npm`, `npx` and `corepack` in `~/.rnpm/shims`, runs the same command
+//! of the version that applies in the current folder, see `manager::active_version`.
+
+use std::{path::Path, process::Command};
+
+use rnpm_core::{
+    config::Config,
+    error::{AppError, AppResult},
+    manager::active_version,
+    node::binary_path,
+    shim::shim_name,
+};
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("rnpm: {}", e.message);
+        std::process::exit(1);
+    }
+}
+
+/// Only returns when the command couldn't be started.
+fn run() -> AppResult<()> {
+    let mut args = std::env::args_os();
+    let argv0 = args.next().unwrap_or_default();
+    let name = shim_name(Path::new(&argv0)).ok_or(AppError::new(format!(
+        "{} is not a shim, run `rnpm shims` to install them",
+        argv0.to_string_lossy()
+    )))?;
+
+    let config = Config::load()?;
+    let active = active_version(&config, &std::env::current_dir()?)?.ok_or(AppError::new(
+        "no node version in use, pick one with `rnpm use`",
+    ))?;
+    let install = config.node_dir.join(&active.version);
+    let binary = binary_path(&install, name);
+    if !binary.exists() {
+        return Err(AppError::new(format!(
+            "node {} has no {name}",
+            active.version
+        )));
+    }
+
+    // what the command starts, npm scripts and globally installed packages, use this version too
+    let bin = binary_path(&install, "node")
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or(install);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path = std::env::join_paths(std::iter::once(bin).chain(std::env::split_paths(&path)))?;
+
+    let mut command = Command::new(&binary);
+    command.args(args).env("PATH", path);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let e = command.exec();
+        Err(AppError::new(format!(
+            "can't run {}: {e}",
+            binary.to_string_lossy()
+        )))
+    }
+
+    #[cfg(windows)]
+    {
+        let status = command
+            .status()
+            .map_err(|e| AppError::new(format!("can't run {}: {e}", binary.to_string_lossy())))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
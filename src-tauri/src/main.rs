@@ -1,11 +1,9 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod config;
-mod error;
 mod node;
+mod settings;
 mod state;
-mod unpack;
 
 use state::AppState;
 use tauri::generate_handler;
@@ -16,11 +14,24 @@ fn main() {
         .invoke_handler(generate_handler![
             node::node_init,
             node::node_list,
+            node::node_list_refresh,
+            node::node_resolve,
+            node::node_project,
+            node::node_project_pick,
             node::node_local_versions,
             node::node_cur_version,
             node::node_set_cur_version,
             node::node_download,
-            node::node_delete
+            node::node_download_cancel,
+            node::node_verify,
+            node::node_verify_all,
+            node::node_reinstall,
+            node::node_delete,
+            settings::config_get,
+            settings::config_set,
+            settings::shell_setup_plan,
+            settings::shell_setup_install,
+            settings::shell_setup_remove
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
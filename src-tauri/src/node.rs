@@ -1,118 +1,188 @@
-use std::str::FromStr;
-
-use globenv::set_path;
-use semver::Version;
-use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use rnpm_core::{
+    config::Config,
+    error::{AppError, AppResult, ErrorKind},
+    lock::HomeLock,
+    manager::{
+        current, install, list_local, list_remote, local_nodes, project, reinstall, resolve_local,
+        resolve_remote, set_current, uninstall, Progress, Project,
+    },
+    node::mark_available,
+    resolve::resolve_all,
+    unpack::clean_staging,
+    verify::{verify_install, VerifyReport},
+};
 use serde_json::Value;
-use tauri::{api::dialog::blocking::confirm, AppHandle, Manager, State};
-use tempfile::tempdir;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio_stream::StreamExt;
-
-use crate::{
-    error::{AppError, AppResult},
-    state::AppState,
-    unpack::unpack,
+use tauri::{
+    api::dialog::blocking::{confirm, FileDialogBuilder},
+    AppHandle, Manager, State,
 };
+use tokio_util::sync::CancellationToken;
 
-mod lts {
-    use serde::{Deserialize, Deserializer};
+use crate::state::{AppState, NodeState};
 
-    #[derive(Deserialize, Debug, PartialEq, Eq)]
-    #[serde(untagged)]
-    enum Lts {
-        Bool(bool),
-        Str(String),
+/// Installed versions are loaded first and don't need the network, the remote list is fetched
+/// in the background. Each phase reports its own failure instead of aborting the others.
+#[tauri::command]
+pub async fn node_init(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
+    clean_interrupted_installs(&state.config().await).await;
+    if let Err(e) = node_local_versions(app.clone(), state.clone()).await {
+        emit_init_error(&app, "local_versions", &e)?;
+    }
+    if let Err(e) = node_cur_version(app.clone(), state.clone()).await {
+        emit_init_error(&app, "cur_version", &e)?;
     }
 
-    impl From<Lts> for Option<String> {
-        fn from(status: Lts) -> Self {
-            match status {
-                Lts::Bool(_) => None,
-                Lts::Str(x) => Some(x),
+    tauri::async_runtime::spawn({
+        let app = app.clone();
+        async move {
+            if let Err(e) = node_verify_all(app.clone(), app.state::<AppState>()).await {
+                let _ = emit_init_error(&app, "verify", &e);
             }
         }
-    }
+    });
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        Ok(Lts::deserialize(deserializer)?.into())
+    tauri::async_runtime::spawn(async move {
+        // failures are reported through `node_list_cache`
+        let _ = node_list(app.clone(), app.state::<AppState>()).await;
+    });
+
+    Ok(serde_json::json!("success"))
+}
+
+/// Remove the staging folders of installs that were cut off, unless another rnpm process holds
+/// `HomeLock` and may be unpacking into one right now.
+async fn clean_interrupted_installs(config: &Config) {
+    let res = match HomeLock::acquire(&config.node_dir, Duration::ZERO).await {
+        Ok(_lock) => clean_staging(&config.node_dir),
+        Err(e) => Err(e),
+    };
+    match res {
+        Ok(()) => {}
+        Err(e) if e.kind == ErrorKind::Busy => {
+            eprintln!("skipped cleaning up interrupted installs: {}", e.message)
+        }
+        Err(e) => eprintln!("can't clean up interrupted installs: {}", e.message),
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Node {
-    pub version: String,
-    #[serde(deserialize_with = "lts::deserialize")]
-    pub lts: Option<String>,
-    // pub files: Vec<String>,
+fn emit_init_error(app: &AppHandle, phase: &str, e: &AppError) -> AppResult<()> {
+    app.emit_all(
+        "node_init_error",
+        serde_json::json!({
+            "phase": phase,
+            "message": e.message
+        }),
+    )?;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn node_init(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
-    node_list(app.clone(), state.clone()).await?;
-    node_local_versions(app.clone(), state.clone()).await?;
-    node_cur_version(app, state).await?;
-    Ok(serde_json::json!("success"))
+pub async fn node_list(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
+    list(app, state, false).await
 }
 
+/// Re-fetch the list from the mirrors, ignoring the TTL of the cached one.
 #[tauri::command]
-pub async fn node_list(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
-    let mut ns = state.node_state.lock().await;
-    let mut nodes = &ns.all;
-
-    if nodes.is_empty() {
-        let url = state.config.list_url();
-        let res = reqwest::get(url).await?.json::<Vec<Node>>().await?;
-        let res = res
-            .into_iter()
-            .filter(|node| {
-                let mut res = false;
-                if let Ok(version) = Version::from_str(&node.version.trim_start_matches('v')) {
-                    if version.major >= 4 {
-                        res = true;
-                    } else {
-                        let minors = [10u64, 12u64];
-                        if version.major == 0 && minors.contains(&version.minor) {
-                            res = true;
-                        }
-                    }
-                }
-
-                return res;
-            })
-            .collect::<Vec<_>>();
+pub async fn node_list_refresh(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
+    list(app, state, true).await
+}
+
+async fn list(app: AppHandle, state: State<'_, AppState>, refresh: bool) -> AppResult<Value> {
+    let config = state.config().await;
 
-        ns.all = res;
-        nodes = &ns.all;
+    // fetched without holding `node_state`, the retries may take a while offline
+    let fetch = refresh || state.node_state.lock().await.all.is_empty();
+    let res = if fetch {
+        let events = ListEvents(&app);
+        Some(list_remote(&config, &state.client().await, refresh, &events).await)
+    } else {
+        None
+    };
+
+    let mut ns = state.node_state.lock().await;
+    if let Some(res) = res {
+        match res {
+            Ok(list) => {
+                ns.all = list.nodes;
+                ns.list_fetched_at = Some(list.fetched_at);
+                ns.list_error = list.error;
+                ns.list_cache_error = list.cache_error;
+            }
+            Err(e) => {
+                ns.list_error = Some(e.message.clone());
+                emit_list_cache(&app, &ns)?;
+                return Err(e);
+            }
+        }
     }
 
-    let json = serde_json::json!(nodes);
+    // the settings deciding this may have changed since the list was loaded
+    mark_available(&config, &mut ns.all);
+
+    let json = serde_json::json!(ns.all);
 
     app.emit_all("node_list", &json)?;
+    emit_list_cache(&app, &ns)?;
 
     Ok(json)
 }
 
+/// The versions a version, range or alias like `^18.17` or `lts/-1` matches, newest first,
+/// among the installed ones when `local` is set.
 #[tauri::command]
-pub async fn node_local_versions(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
-    tokio::fs::create_dir_all(&state.config.node_dir).await?;
-    let mut res = tokio::fs::read_dir(&state.config.node_dir).await?;
-
-    let mut local_versions = vec![];
-    while let Some(entry) = res.next_entry().await? {
-        let metadata = entry.metadata().await?;
-        if metadata.is_dir() {
-            if let Ok(dir_name) = entry.file_name().into_string() {
-                let version = dir_name.trim_start_matches('v');
-                if let Ok(_) = Version::from_str(version) {
-                    local_versions.push(dir_name);
-                }
-            }
-        }
+pub async fn node_resolve(
+    spec: String,
+    local: bool,
+    state: State<'_, AppState>,
+) -> AppResult<Value> {
+    let matches = if local {
+        resolve_all(&spec, &local_nodes(&state.config().await)?)?
+    } else {
+        resolve_all(&spec, &state.node_state.lock().await.all)?
+    };
+
+    Ok(serde_json::json!({
+        "version": matches.first(),
+        "matches": matches
+    }))
+}
+
+/// Reports retries of the list as `node_list_retry`.
+struct ListEvents<'a>(&'a AppHandle);
+
+impl Progress for ListEvents<'_> {
+    fn retry(&self, attempt: u32, retries: u32, e: &AppError) -> AppResult<()> {
+        self.0.emit_all(
+            "node_list_retry",
+            serde_json::json!({
+                "attempt": attempt,
+                "retries": retries,
+                "message": e.message
+            }),
+        )?;
+        Ok(())
     }
+}
+
+/// Tell the UI how old the list is, why it couldn't be refreshed, and why it couldn't be cached.
+fn emit_list_cache(app: &AppHandle, ns: &NodeState) -> AppResult<()> {
+    app.emit_all(
+        "node_list_cache",
+        serde_json::json!({
+            "fetched_at": ns.list_fetched_at,
+            "error": ns.list_error,
+            "cache_error": ns.list_cache_error
+        }),
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn node_local_versions(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
+    let config = state.config().await;
+    let local_versions = list_local(&config)?;
 
     let mut ns = state.node_state.lock().await;
     ns.local_versions = local_versions.clone();
@@ -126,18 +196,11 @@ pub async fn node_local_versions(app: AppHandle, state: State<'_, AppState>) ->
 
 #[tauri::command]
 pub async fn node_cur_version(app: AppHandle, state: State<'_, AppState>) -> AppResult<Value> {
-    let mut file = tokio::fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(&state.config.node_dir.join("version"))
-        .await?;
-    let mut version = Vec::new();
-    file.read_to_end(&mut version).await?;
-    let version = String::from_utf8(version)?;
-    let json = serde_json::json!(version);
-
-    if let Ok(_) = Version::from_str(version.trim_start_matches('v')) {
+    let config = state.config().await;
+    let version = current(&config)?;
+    let json = serde_json::json!(version.clone().unwrap_or_default());
+
+    if let Some(version) = version {
         let ns = state.node_state.lock().await;
         if ns.local_versions.contains(&version) {
             app.emit_all("node_cur_version", json.clone())?;
@@ -153,17 +216,9 @@ pub async fn node_set_cur_version(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> AppResult<()> {
-    let mut file = tokio::fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&state.config.node_dir.join("version"))
-        .await?;
-
-    file.write_all(version.as_bytes()).await?;
-
-    set_path(r#"$HOME/.rnpm/$(cat "$HOME/.rnpm/version")/bin:$PATH"#)?;
+    let config = state.config().await;
+    let version = resolve_local(&config, &version)?;
+    set_current(&config, &version).await?;
 
     node_cur_version(app, state).await?;
     Ok(())
@@ -175,39 +230,181 @@ pub async fn node_download(
     state: State<'_, AppState>,
     app: AppHandle,
 ) -> AppResult<()> {
-    let temp_dir = tempdir()?;
-    let file_path = temp_dir.path().join(state.config.filename(&version));
-    let mut file = tokio::fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&file_path)
-        .await?;
-
-    let url = state.config.download_url(&version);
-    let response = reqwest::Client::new().get(url).send().await?;
-    let total = response.content_length().unwrap_or(0);
-    let mut progress = 0u64;
-    let mut body = response.bytes_stream();
-    let event_name_progrss = format!("node_download:{}", version.replace(".", "-"));
-    while let Some(chunk) = body.next().await {
-        let chunk = chunk?;
-        progress += chunk.len() as u64;
-        file.write_all(&chunk).await?;
-        app.emit_all(
-            &event_name_progrss,
+    start_download(version, false, state, app).await
+}
+
+/// `node_download`, over the install there is when `replace`.
+async fn start_download(
+    version: String,
+    replace: bool,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> AppResult<()> {
+    let event_name = download_event(&version);
+    let cancel = CancellationToken::new();
+    state
+        .downloads
+        .lock()
+        .await
+        .insert(version.clone(), cancel.clone());
+
+    let res = download(&version, replace, &event_name, &cancel, &state, &app).await;
+    state.downloads.lock().await.remove(&version);
+
+    if let Err(e) = res {
+        let payload = if e.kind == ErrorKind::Cancelled {
+            serde_json::json!({ "type": "cancelled" })
+        } else {
             serde_json::json!({
+                "type": "error",
+                "kind": e.kind,
+                "message": e.message
+            })
+        };
+        app.emit_all(&event_name, payload)?;
+        return Err(e);
+    }
+
+    node_local_versions(app, state).await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn node_download_cancel(version: String, state: State<'_, AppState>) -> AppResult<()> {
+    if let Some(cancel) = state.downloads.lock().await.get(&version) {
+        cancel.cancel();
+    }
+
+    Ok(())
+}
+
+async fn download(
+    version: &str,
+    replace: bool,
+    event_name: &str,
+    cancel: &CancellationToken,
+    state: &AppState,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let config = state.config().await;
+    let acquire = state.download_queue.acquire(version);
+    emit_download_queue(app, state)?;
+    let permit = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => None,
+        permit = acquire => Some(permit),
+    };
+    emit_download_queue(app, state)?;
+    let _permit = permit.ok_or(AppError::with_kind(
+        ErrorKind::Cancelled,
+        format!("download of {version} cancelled"),
+    ))?;
+
+    let client = state.client().await;
+    let events = DownloadEvents { app, event_name };
+    let version = resolve_remote(&config, &client, version, &events).await?;
+    if replace {
+        reinstall(&config, &client, &version, &events, cancel).await
+    } else {
+        install(&config, &client, &version, &events, cancel).await
+    }
+}
+
+/// Reports the progress of a download on its `node_download:*` event.
+struct DownloadEvents<'a> {
+    app: &'a AppHandle,
+    event_name: &'a str,
+}
+
+impl Progress for DownloadEvents<'_> {
+    fn retry(&self, attempt: u32, retries: u32, e: &AppError) -> AppResult<()> {
+        self.app.emit_all(
+            self.event_name,
+            serde_json::json!({
+                "type": "retry",
+                "attempt": attempt,
+                "retries": retries,
+                "message": e.message
+            }),
+        )?;
+        Ok(())
+    }
+
+    fn download(&self, _filename: &str, total: u64, progress: u64) -> AppResult<()> {
+        self.app.emit_all(
+            self.event_name,
+            serde_json::json!({
+                "type": "progress",
                 "total": total,
                 "progress": progress
             }),
-        )?
+        )?;
+        Ok(())
     }
 
-    unpack(version, file_path, state.config.node_dir.clone())?;
+    fn unpacking(&self) -> AppResult<()> {
+        self.app
+            .emit_all(self.event_name, serde_json::json!({ "type": "unpacking" }))?;
+        Ok(())
+    }
+}
 
-    node_local_versions(app, state).await?;
+fn download_event(version: &str) -> String {
+    format!("node_download:{}", version.replace('.', "-"))
+}
+
+/// Tell every queued download its position in line.
+fn emit_download_queue(app: &AppHandle, state: &AppState) -> AppResult<()> {
+    for (index, version) in state.download_queue.waiting().iter().enumerate() {
+        app.emit_all(
+            &download_event(version),
+            serde_json::json!({
+                "type": "queued",
+                "position": index + 1
+            }),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Check an installed version is complete and runs, see `verify_install`.
+#[tauri::command]
+pub async fn node_verify(
+    version: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<VerifyReport> {
+    let dir = state.config().await.node_dir.join(&version);
+    let report = tokio::task::spawn_blocking(move || verify_install(&dir, &version)).await?;
+    app.emit_all("node_verify", &report)?;
+    Ok(report)
+}
 
+/// `node_verify` every installed version.
+#[tauri::command]
+pub async fn node_verify_all(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<VerifyReport>> {
+    let local_versions = state.node_state.lock().await.local_versions.clone();
+    let mut reports = vec![];
+    for version in local_versions {
+        reports.push(node_verify(version, app.clone(), state.clone()).await?);
+    }
+    Ok(reports)
+}
+
+/// Replace a broken install with a fresh download, keeping it when the download fails.
+#[tauri::command]
+pub async fn node_reinstall(
+    version: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> AppResult<()> {
+    start_download(version.clone(), true, state.clone(), app.clone()).await?;
+    node_verify(version, app, state).await?;
     Ok(())
 }
 
@@ -219,15 +416,45 @@ pub async fn node_delete(
 ) -> AppResult<()> {
     let window = app
         .get_focused_window()
-        .ok_or(AppError("no main window".to_string()))?;
+        .ok_or(AppError::new("no main window"))?;
 
     let message = format!("Will the local node version {version} be deleted?");
     let res = confirm(Some(&window), "confirm deletion？", message);
 
     if res {
-        std::fs::remove_dir_all(state.config.node_dir.join(version))?;
+        uninstall(&state.config().await, &version).await?;
         node_local_versions(app, state).await?;
     }
 
     Ok(())
 }
+
+/// The version the project around `dir` pins in `.nvmrc`, `.node-version` or `engines.node`,
+/// around the folder the app was started in by default.
+#[tauri::command]
+pub async fn node_project(
+    dir: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Option<Project>> {
+    let dir = match dir {
+        Some(dir) => dir.into(),
+        None => std::env::current_dir()?,
+    };
+    let config = state.config().await;
+    let ns = state.node_state.lock().await;
+    project(&config, &dir, &ns.all)
+}
+
+/// Ask for a project folder, `None` when the dialog is dismissed.
+#[tauri::command]
+pub async fn node_project_pick(app: AppHandle) -> AppResult<Option<String>> {
+    let window = app
+        .get_focused_window()
+        .ok_or(AppError::new("no main window"))?;
+
+    let dir = FileDialogBuilder::new()
+        .set_title("project folder")
+        .set_parent(&window)
+        .pick_folder();
+    Ok(dir.map(|dir| dir.to_string_lossy().to_string()))
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use rnpm_core::{
+    config::Config,
+    error::{AppError, AppResult},
+    shell::{self, find_rnpm, Shell, ShellSetup},
+};
+use tauri::{AppHandle, State};
+
+use crate::{
+    node::{node_cur_version, node_list, node_list_refresh, node_local_versions},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn config_get(state: State<'_, AppState>) -> AppResult<Config> {
+    Ok(state.config().await)
+}
+
+#[tauri::command]
+pub async fn config_set(
+    config: Config,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Config> {
+    config.validate()?;
+    let client = config.http_client()?;
+    config.save()?;
+
+    let prev = std::mem::replace(&mut *state.config.write().await, config.clone());
+    *state.client.write().await = client;
+    state
+        .download_queue
+        .set_concurrency(config.download_concurrency);
+
+    if prev.node_dir != config.node_dir {
+        node_local_versions(app.clone(), state.clone()).await?;
+        node_cur_version(app.clone(), state.clone()).await?;
+    }
+
+    if prev.mirrors != config.mirrors {
+        node_list_refresh(app, state).await?;
+    } else if prev.rosetta != config.rosetta {
+        node_list(app, state).await?;
+    }
+
+    Ok(config)
+}
+
+/// What the shell integration adds to the startup file of each shell, and whether it's there.
+#[tauri::command]
+pub async fn shell_setup_plan(state: State<'_, AppState>) -> AppResult<Vec<ShellSetup>> {
+    let node_dir = state.config().await.node_dir;
+    let home = home_dir()?;
+    let rnpm = find_rnpm(&std::env::current_exe()?);
+    Shell::ALL
+        .into_iter()
+        .map(|shell| shell::plan(&home, &node_dir, rnpm.as_deref(), shell))
+        .collect()
+}
+
+/// Without the command line next to the app or on `PATH`, only puts `current` on `PATH`.
+#[tauri::command]
+pub async fn shell_setup_install(
+    state: State<'_, AppState>,
+    shell: Shell,
+) -> AppResult<Vec<ShellSetup>> {
+    let node_dir = state.config().await.node_dir;
+    let rnpm = find_rnpm(&std::env::current_exe()?);
+    shell::install(&home_dir()?, &node_dir, rnpm.as_deref(), shell)?;
+    shell_setup_plan(state).await
+}
+
+#[tauri::command]
+pub async fn shell_setup_remove(
+    state: State<'_, AppState>,
+    shell: Shell,
+) -> AppResult<Vec<ShellSetup>> {
+    shell::remove(&home_dir()?, shell)?;
+    shell_setup_plan(state).await
+}
+
+fn home_dir() -> AppResult<PathBuf> {
+    dirs::home_dir().ok_or(AppError::new("no home folder"))
+}
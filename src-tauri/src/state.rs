@@ -1,23 +1,60 @@
-use tauri::async_runtime::Mutex;
+use std::collections::HashMap;
 
-use crate::{config::Config, node::Node};
+use reqwest::Client;
+use rnpm_core::{config::Config, node::Node, queue::DownloadQueue};
+use tauri::async_runtime::{Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Default)]
 pub struct NodeState {
     pub all: Vec<Node>,
     pub local_versions: Vec<String>,
+    /// When `all` was fetched from a mirror, seconds since the unix epoch.
+    pub list_fetched_at: Option<u64>,
+    /// Why `all` couldn't be refreshed and is served from the cache.
+    pub list_error: Option<String>,
+    /// Why a freshly fetched `all` couldn't be written to the cache.
+    pub list_cache_error: Option<String>,
 }
 
 pub struct AppState {
-    pub config: Config,
+    pub config: RwLock<Config>,
+    /// Shared by every network call, rebuilt whenever the settings change.
+    pub client: RwLock<Client>,
     pub node_state: Mutex<NodeState>,
+    pub downloads: Mutex<HashMap<String, CancellationToken>>,
+    pub download_queue: DownloadQueue,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let config = Config::load().unwrap_or_else(|e| {
+            eprintln!(
+                "invalid {}: {}",
+                Config::path().to_string_lossy(),
+                e.message
+            );
+            Config::default()
+        });
+        let client = config.http_client().unwrap_or_else(|e| {
+            eprintln!("invalid http settings: {}", e.message);
+            Client::new()
+        });
         Self {
-            config: Config::new(),
+            client: RwLock::new(client),
+            download_queue: DownloadQueue::new(config.download_concurrency),
+            config: RwLock::new(config),
             node_state: Mutex::new(NodeState::default()),
+            downloads: Mutex::new(HashMap::new()),
         }
     }
+
+    /// A snapshot of the current settings.
+    pub async fn config(&self) -> Config {
+        self.config.read().await.clone()
+    }
+
+    pub async fn client(&self) -> Client {
+        self.client.read().await.clone()
+    }
 }
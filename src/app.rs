@@ -3,8 +3,10 @@ use leptos::*;
 use crate::components::header::HeaderView;
 use crate::components::node_version_list::NodeVersionListView;
 use crate::components::options::OptionsView;
+use crate::components::project::ProjectView;
+use crate::components::settings::SettingsView;
 use crate::error::StrError;
-use crate::node::{Node, NodeStatus};
+use crate::node::{merge_nodes, Node, NodeStatus};
 use crate::state::{DisplayMode, State};
 use crate::tauri::{tauri_invoke, tauri_on};
 
@@ -29,19 +31,76 @@ pub fn App() -> impl IntoView {
             tauri_on::<serde_json::Value>("node_list", move |event| {
                 let payload = event.payload;
                 if let Ok(nodes) = serde_json::from_value::<Vec<Node>>(payload) {
-                    state.all_nodes.set(nodes);
+                    let local_versions = state.local_versions.get_untracked();
+                    state
+                        .all_nodes
+                        .update(|all| *all = merge_nodes(all, nodes, &local_versions));
                 };
             })
             .await
         },
     );
 
+    create_resource(
+        || (),
+        move |_| async move {
+            tauri_on::<serde_json::Value>("node_list_retry", move |event| {
+                let payload = event.payload;
+                let attempt = payload["attempt"].as_u64().unwrap_or(0);
+                let retries = payload["retries"].as_u64().unwrap_or(0);
+                state.list_retry.set(Some((attempt, retries)));
+            })
+            .await
+        },
+    );
+
+    create_resource(
+        || (),
+        move |_| async move {
+            tauri_on::<serde_json::Value>("node_init_error", move |event| {
+                let payload = event.payload;
+                let phase = payload["phase"].as_str().unwrap_or_default().to_string();
+                let message = payload["message"].as_str().unwrap_or_default().to_string();
+                state
+                    .init_errors
+                    .update(|errors| errors.push((phase, message)));
+            })
+            .await
+        },
+    );
+
+    create_resource(
+        || (),
+        move |_| async move {
+            tauri_on::<serde_json::Value>("node_list_cache", move |event| {
+                let payload = event.payload;
+                state.list_retry.set(None);
+                state.list_fetched_at.set(payload["fetched_at"].as_u64());
+                state
+                    .list_error
+                    .set(payload["error"].as_str().map(|e| e.to_string()));
+                state
+                    .list_cache_error
+                    .set(payload["cache_error"].as_str().map(|e| e.to_string()));
+            })
+            .await
+        },
+    );
+
     create_resource(
         || (),
         move |_| async move {
             tauri_on::<serde_json::Value>("node_local_versions", move |event| {
                 let payload = event.payload;
                 if let Ok(local_versions) = serde_json::from_value::<Vec<String>>(payload) {
+                    state.all_nodes.update(|all| {
+                        let remote = all
+                            .iter()
+                            .filter(|node| !node.local_only)
+                            .cloned()
+                            .collect();
+                        *all = merge_nodes(all, remote, &local_versions);
+                    });
                     state.local_versions.set(local_versions);
                 };
             })
@@ -49,6 +108,28 @@ pub fn App() -> impl IntoView {
         },
     );
 
+    create_resource(
+        || (),
+        move |_| async move {
+            tauri_on::<serde_json::Value>("node_verify", move |event| {
+                let payload = event.payload;
+                let Some(version) = payload["version"].as_str() else {
+                    return;
+                };
+                let problems = serde_json::from_value::<Vec<String>>(payload["problems"].clone())
+                    .unwrap_or_default();
+                state.broken.update(|broken| {
+                    if problems.is_empty() {
+                        broken.remove(version);
+                    } else {
+                        broken.insert(version.to_string(), problems);
+                    }
+                });
+            })
+            .await
+        },
+    );
+
     create_resource(
         || (),
         move |_| async move {
@@ -69,9 +150,10 @@ pub fn App() -> impl IntoView {
                 state.all_nodes.get(),
                 state.local_versions.get(),
                 state.cur_version.get(),
+                state.broken.get(),
             )
         },
-        move |(all_nodes, local_versions, cur_version), _, _| {
+        move |(all_nodes, local_versions, cur_version, broken), _, _| {
             for node in all_nodes {
                 let mut status = NodeStatus::Pendding;
                 let version = &node.version;
@@ -84,9 +166,18 @@ pub fn App() -> impl IntoView {
                             status = NodeStatus::CurVer;
                         }
                     }
+
+                    if broken.contains_key(version) {
+                        status = NodeStatus::Broken;
+                    }
+                }
+
+                let cur_status = node.status.get_untracked();
+                if status == NodeStatus::Pendding && cur_status.is_busy() {
+                    continue;
                 }
 
-                if status != node.status.get_untracked() {
+                if status != cur_status {
                     node.status.set(status);
                 }
             }
@@ -102,17 +193,17 @@ pub fn App() -> impl IntoView {
                 state.display_mod.get(),
                 state.local_versions.get(),
                 state.filter_version.get(),
+                state.filter_matches.get(),
             )
         },
-        move |(all_nodes, display_mode, local_versions, filter_version), _, _| {
+        move |(all_nodes, display_mode, local_versions, filter_version, filter_matches), _, _| {
             for node in all_nodes {
-                let mut hidden = false;
-
                 let version = &node.version;
 
-                if !version.contains(filter_version) {
-                    hidden = true;
-                }
+                let mut hidden = match filter_matches {
+                    Some(matches) => !matches.contains(version),
+                    None => !version.contains(filter_version),
+                };
 
                 if display_mode == &DisplayMode::Local && !local_versions.contains(version) {
                     hidden = true;
@@ -128,7 +219,14 @@ pub fn App() -> impl IntoView {
 
     view! {
         <main class="flex flex-col h-screen gap-6 bg-white p-4">
-            <Suspense fallback=move || view! { <p class="m-auto text-3xl text-gray-400">"Loading..."</p> }>
+            <Suspense fallback=move || view! {
+                <div class="m-auto flex flex-col items-center gap-2">
+                    <p class="text-3xl text-gray-400">"Loading..."</p>
+                    {move || state.list_retry.get().map(|(attempt, retries)| view! {
+                        <p class="text-yellow-500">"retrying (" {attempt} "/" {retries} ")"</p>
+                    })}
+                </div>
+            }>
                 <ErrorBoundary
                     fallback=move |errors| view! {
                         <div class="m-auto flex flex-col max-h-[75%] w-2/3 text-red-500">
@@ -148,7 +246,24 @@ pub fn App() -> impl IntoView {
                         {move || init.get()}
                         <HeaderView/>
                         <OptionsView/>
+                        <For
+                            each=move || state.init_errors.get()
+                            key=|(phase, _)| phase.clone()
+                            let:error>
+                            <div class="flex items-center justify-between rounded-md bg-red-50 px-3 py-1 text-red-500">
+                                <p class="break-words">{error.1}</p>
+                                <button
+                                    class="text-gray-500"
+                                    on:click=move |_| state.init_errors.update(|errors| errors.retain(|(phase, _)| phase != &error.0))>
+                                    Dismiss
+                                </button>
+                            </div>
+                        </For>
+                        <ProjectView/>
                         <NodeVersionListView/>
+                        <Show when=move || state.show_settings.get()>
+                            <SettingsView/>
+                        </Show>
                 </ErrorBoundary>
             </Suspense>
         </main>
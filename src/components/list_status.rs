@@ -0,0 +1,72 @@
+use leptos::*;
+
+use crate::error::StrError;
+use crate::state::State;
+use crate::tauri::tauri_invoke;
+
+#[component]
+pub fn ListStatusView() -> impl IntoView {
+    let state = use_context::<State>().expect("get state failed");
+
+    let refresh = create_action(move |_: &()| async move {
+        if let Err(e) = tauri_invoke!(serde_json::Value, "node_list_refresh").await {
+            state.list_error.set(Some(StrError::from(e).to_string()));
+        }
+    });
+
+    let age = move || {
+        state.list_fetched_at.get().map(|fetched_at| {
+            let now = (js_sys::Date::now() / 1000.0) as u64;
+            format_age(now.saturating_sub(fetched_at))
+        })
+    };
+
+    view! {
+        <div class="flex flex-row items-center gap-2 text-sm">
+            {move || match (state.list_error.get(), age(), state.list_retry.get()) {
+                (_, None, Some((attempt, retries))) => view! {
+                    <span class="text-yellow-500">"fetching list, retrying (" {attempt} "/" {retries} ")"</span>
+                }.into_view(),
+                (Some(error), Some(age), _) => view! {
+                    <span class="text-yellow-500" title=error>"offline, list from " {age}</span>
+                }.into_view(),
+                (Some(error), None, _) => view! {
+                    <span class="text-red-500" title=error>"remote list unavailable"</span>
+                }.into_view(),
+                (None, Some(age), _) => view! {
+                    <span class="text-gray-400">"updated " {age}</span>
+                }.into_view(),
+                (None, None, _) => view! {
+                    <span class="text-gray-400">"loading list..."</span>
+                }.into_view(),
+            }}
+            {move || state.list_cache_error.get().map(|error| view! {
+                <span class="text-yellow-500" title=error>"not cached"</span>
+            })}
+
+            <button
+                class="text-gray-500 hover:text-blue-500 disabled:text-gray-300"
+                disabled=move || refresh.pending().get()
+                on:click=move |_| refresh.dispatch(())>
+                {move || if refresh.pending().get() { "refreshing..." } else { "refresh" }}
+            </button>
+        </div>
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+#[test]
+fn test_format_age() {
+    assert_eq!(format_age(5), "just now");
+    assert_eq!(format_age(150), "2m ago");
+    assert_eq!(format_age(7200), "2h ago");
+    assert_eq!(format_age(3 * 86400 + 10), "3d ago");
+}
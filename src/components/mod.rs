@@ -1,4 +1,7 @@
 pub mod header;
+pub mod list_status;
 pub mod node_version_item;
 pub mod node_version_list;
 pub mod options;
+pub mod project;
+pub mod settings;
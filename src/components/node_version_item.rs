@@ -2,6 +2,7 @@ use leptos::*;
 
 use crate::{
     node::NodeStatus,
+    state::State,
     tauri::{tauri_invoke, tauri_on},
 };
 
@@ -9,9 +10,14 @@ use crate::{
 pub fn NodeVersionItemView(
     version: String,
     lts: Option<String>,
+    available: bool,
     hidden: RwSignal<bool>,
     status: RwSignal<NodeStatus>,
 ) -> impl IntoView {
+    let state = use_context::<State>().expect("get state failed");
+    let error = create_rw_signal(None::<String>);
+    let retrying = create_rw_signal(None::<(u64, u64)>);
+
     let ver = version.clone();
     create_resource(
         || (),
@@ -21,12 +27,38 @@ pub fn NodeVersionItemView(
             async move {
                 tauri_on::<serde_json::Value>(&event_name_progrss, move |event| {
                     let payload = event.payload;
-                    let total = payload["total"].as_u64().unwrap_or(0);
-                    let progress = payload["progress"].as_u64().unwrap_or(0);
-                    let progress = (progress * 100 / total) as usize;
-                    if status.get_untracked() != NodeStatus::Downloading(progress) {
-                        status.set(NodeStatus::Downloading(progress));
-                    };
+                    if payload["type"].as_str() == Some("retry") {
+                        let attempt = payload["attempt"].as_u64().unwrap_or(0);
+                        let retries = payload["retries"].as_u64().unwrap_or(0);
+                        retrying.set(Some((attempt, retries)));
+                        return;
+                    }
+
+                    if retrying.get_untracked().is_some() {
+                        retrying.set(None);
+                    }
+
+                    match payload["type"].as_str() {
+                        Some("progress") => {
+                            let total = payload["total"].as_u64().unwrap_or(0);
+                            let progress = payload["progress"].as_u64().unwrap_or(0);
+                            let progress = (progress * 100 / total.max(1)) as usize;
+                            if status.get_untracked() != NodeStatus::Downloading(progress) {
+                                status.set(NodeStatus::Downloading(progress));
+                            };
+                        }
+                        Some("queued") => {
+                            let position = payload["position"].as_u64().unwrap_or(0) as usize;
+                            status.set(NodeStatus::Queued(position));
+                        }
+                        Some("unpacking") => status.set(NodeStatus::Unpacking),
+                        Some("cancelled") => status.set(NodeStatus::Pendding),
+                        Some("error") => {
+                            let message = payload["message"].as_str().unwrap_or_default();
+                            error.set(Some(message.to_string()));
+                        }
+                        _ => {}
+                    }
                 })
                 .await
             }
@@ -35,7 +67,8 @@ pub fn NodeVersionItemView(
 
     let ver = version.clone();
     let download = create_action(move |_: &()| {
-        status.set(NodeStatus::Downloading(0));
+        error.set(None);
+        status.set(NodeStatus::Queued(0));
         let ver = ver.to_owned();
         async move {
             if tauri_invoke!("node_download", &serde_json::json!({"version": ver}))
@@ -47,6 +80,38 @@ pub fn NodeVersionItemView(
         }
     });
 
+    let ver = version.clone();
+    let reinstall = create_action(move |_: &()| {
+        error.set(None);
+        status.set(NodeStatus::Queued(0));
+        let ver = ver.to_owned();
+        async move {
+            if tauri_invoke!("node_reinstall", &serde_json::json!({"version": ver}))
+                .await
+                .is_err()
+            {
+                status.set(NodeStatus::Pendding);
+            }
+        }
+    });
+
+    let ver = version.clone();
+    let problems = Signal::derive(move || {
+        state
+            .broken
+            .with(|broken| broken.get(&ver).map(|problems| problems.join("\n")))
+            .unwrap_or_default()
+    });
+
+    let ver = version.clone();
+    let cancel = create_action(move |_: &()| {
+        let ver = ver.to_owned();
+        async move {
+            let _ =
+                tauri_invoke!("node_download_cancel", &serde_json::json!({"version": ver})).await;
+        }
+    });
+
     let ver = version.clone();
     let delete = create_action(move |_: &()| {
         let ver = ver.to_owned();
@@ -72,8 +137,16 @@ pub fn NodeVersionItemView(
             <div class="text-2xl text-blue-500">{version}</div>
             <div class="rounded-sm bg-blue-500 px-1 text-white">{lts}</div>
 
+            {move || error.get().map(|message| view! {
+                <div class="truncate text-sm text-red-500" title=message.clone()>{message}</div>
+            })}
+
             <div class="ml-auto mr-2 flex flex-row gap-3 *:hover:cursor-pointer">
-                <Show when=move || status.get() == NodeStatus::Pendding>
+                <Show when=move || !available && status.get() == NodeStatus::Pendding>
+                    <div class="text-0.5xl text-gray-400" title="no build for this platform">unavailable</div>
+                </Show>
+
+                <Show when=move || available && status.get() == NodeStatus::Pendding>
                     <div on:click=move|_|download.dispatch(())>
                         <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="h-5 w-5">
                             <path stroke-linecap="round" stroke-linejoin="round" d="M3 16.5v2.25A2.25 2.25 0 0 0 5.25 21h13.5A2.25 2.25 0 0 0 21 18.75V16.5M16.5 12 12 16.5m0 0L7.5 12m4.5 4.5V3" />
@@ -81,14 +154,45 @@ pub fn NodeVersionItemView(
                     </div>
                 </Show>
 
+                {move || retrying.get().map(|(attempt, retries)| view! {
+                    <div class="text-0.5xl text-yellow-500">"retrying (" {attempt} "/" {retries} ")"</div>
+                })}
+
                 {move || match status.get() {
+                    NodeStatus::Queued(0) => Some(view! {
+                        <div class="text-0.5xl text-gray-400">queued</div>
+                    }.into_view()),
+                    NodeStatus::Queued(position) => Some(view! {
+                        <div class="text-0.5xl text-gray-400">queued #{position}</div>
+                    }.into_view()),
                     NodeStatus::Downloading(progress) => Some(view! {
                         <div class="text-0.5xl">{progress}%</div>
                     }.into_view()),
+                    NodeStatus::Unpacking => Some(view! {
+                        <div class="text-0.5xl text-gray-400">unpacking</div>
+                    }.into_view()),
                     _ => None,
                 }}
 
-                <Show when=move || status.get() == NodeStatus::Ready>
+                <Show when=move || matches!(status.get(), NodeStatus::Queued(_) | NodeStatus::Downloading(_))>
+                    <div on:click=move|_|cancel.dispatch(())>
+                        <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="h-5 w-5">
+                            <path stroke-linecap="round" stroke-linejoin="round" d="M6 18 18 6M6 6l12 12" />
+                        </svg>
+                    </div>
+                </Show>
+
+                <Show when=move || status.get() == NodeStatus::Broken>
+                    <div class="text-0.5xl text-red-500" title=problems>broken</div>
+
+                    <div title="reinstall" on:click=move|_|reinstall.dispatch(())>
+                        <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="h-5 w-5">
+                            <path stroke-linecap="round" stroke-linejoin="round" d="M16.023 9.348h4.992v-.001M2.985 19.644v-4.992m0 0h4.992m-4.993 0 3.181 3.183a8.25 8.25 0 0 0 13.803-3.7M4.031 9.865a8.25 8.25 0 0 1 13.803-3.7l3.181 3.182m0-4.991v4.99" />
+                        </svg>
+                    </div>
+                </Show>
+
+                <Show when=move || matches!(status.get(), NodeStatus::Ready | NodeStatus::Broken)>
                     <div on:click=move|_|delete.dispatch(())>
                         <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="h-5 w-5">
                             <path stroke-linecap="round" stroke-linejoin="round" d="m14.74 9-.346 9m-4.788 0L9.26 9m9.968-3.21c.342.052.682.107 1.022.166m-1.022-.165L18.16 19.673a2.25 2.25 0 0 1-2.244 2.077H8.084a2.25 2.25 0 0 1-2.244-2.077L4.772 5.79m14.456 0a48.108 48.108 0 0 0-3.478-.397m-12 .562c.34-.059.68-.114 1.022-.165m0 0a48.11 48.11 0 0 1 3.478-.397m7.5 0v-.916c0-1.18-.91-2.164-2.09-2.201a51.964 51.964 0 0 0-3.32 0c-1.18.037-2.09 1.022-2.09 2.201v.916m7.5 0a48.667 48.667 0 0 0-7.5 0" />
@@ -17,6 +17,7 @@ pub fn NodeVersionListView() -> impl IntoView {
                 <NodeVersionItemView
                     version=child.version
                     lts=child.lts
+                    available=child.available
                     hidden=child.hidden
                     status=child.status />
             </For>
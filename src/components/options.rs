@@ -1,6 +1,16 @@
 use leptos::*;
+use serde::Deserialize;
 
+use crate::components::list_status::ListStatusView;
+use crate::components::project::pick_project_dir;
+use crate::error::StrError;
 use crate::state::{DisplayMode, State};
+use crate::tauri::tauri_invoke;
+
+#[derive(Deserialize)]
+struct Resolution {
+    matches: Vec<String>,
+}
 
 #[component]
 pub fn OptionsView() -> impl IntoView {
@@ -11,13 +21,69 @@ pub fn OptionsView() -> impl IntoView {
         state.filter_version.set(v);
     };
 
+    // ranges are resolved by the backend, among the installed versions in the local view
+    let _ = watch(
+        move || {
+            (
+                state.filter_range.get(),
+                state.filter_version.get(),
+                state.display_mod.get(),
+                state.all_nodes.with(Vec::len),
+                state.local_versions.get(),
+            )
+        },
+        move |(range, spec, display_mode, _, _), _, _| {
+            if !range || spec.trim().is_empty() {
+                state.filter_matches.set(None);
+                state.filter_error.set(None);
+                return;
+            }
+
+            let spec = spec.clone();
+            let args = serde_json::json!({
+                "spec": spec,
+                "local": display_mode == &DisplayMode::Local
+            });
+            spawn_local(async move {
+                let res = tauri_invoke!(Resolution, "node_resolve", &args).await;
+                // the filter changed while this was resolving
+                if state.filter_version.get_untracked() != spec {
+                    return;
+                }
+                match res {
+                    Ok(resolution) => {
+                        state.filter_matches.set(Some(resolution.matches));
+                        state.filter_error.set(None);
+                    }
+                    Err(e) => {
+                        state.filter_matches.set(Some(vec![]));
+                        state.filter_error.set(Some(StrError::from(e).to_string()));
+                    }
+                }
+            });
+        },
+        true,
+    );
+
     view! {
         <div class="flex flex-row items-center gap-6 self-center">
-            <input
-                class="rounded-md border-2 border-gray-400 px-3 py-1 text-gray-700 outline-none focus:border-blue-400"
-                placeholder="version filter"
-                prop:value=state.filter_version
-                on:input=update_filter_version />
+            <div class="flex flex-row items-center gap-2">
+                <input
+                    class="rounded-md border-2 border-gray-400 px-3 py-1 text-gray-700 outline-none focus:border-blue-400"
+                    class:border-red-400=move || state.filter_error.get().is_some()
+                    title=move || state.filter_error.get()
+                    placeholder=move || if state.filter_range.get() { "18, >=16 <20, lts/*" } else { "version filter" }
+                    prop:value=state.filter_version
+                    on:input=update_filter_version />
+
+                <label class="flex flex-row items-center gap-1 text-sm text-gray-500" title="read the filter as a semver range or an alias like latest, lts/* or lts/-1">
+                    <input
+                        type="checkbox"
+                        prop:checked=state.filter_range
+                        on:change=move |ev| state.filter_range.set(event_target_checked(&ev)) />
+                    "range"
+                </label>
+            </div>
 
             <div class="flex flex-row *:flex *:w-24 *:items-center *:justify-center *:border-2 *:text-gray-500 [&>div+div]:-ml-0.5 [&>div:first-child]:rounded-bl-md [&>div:first-child]:rounded-tl-md [&>div:has(input:checked)]:z-10 [&>div:has(input:checked)]:border-blue-500 [&>div:has(input:checked)]:text-blue-500 [&>div:last-child]:rounded-br-md [&>div:last-child]:rounded-tr-md [&_input]:hidden">
                 <div>
@@ -42,6 +108,26 @@ pub fn OptionsView() -> impl IntoView {
                     <label for="Local">Local</label>
                 </div>
             </div>
+
+            <ListStatusView/>
+
+            <div
+                class="text-sm text-gray-500 hover:cursor-pointer hover:text-blue-500"
+                title="look for .nvmrc, .node-version or engines.node in a project folder"
+                on:click=move |_| spawn_local(async move {
+                    if let Err(e) = pick_project_dir(state.project_dir).await {
+                        leptos::logging::error!("{e}");
+                    }
+                })>
+                "project"
+            </div>
+
+            <div class="text-gray-500 hover:cursor-pointer hover:text-blue-500" on:click=move|_|state.show_settings.set(true)>
+                <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="h-6 w-6">
+                    <path stroke-linecap="round" stroke-linejoin="round" d="M9.594 3.94c.09-.542.56-.94 1.11-.94h2.593c.55 0 1.02.398 1.11.94l.213 1.281c.063.374.313.686.645.87.074.04.147.083.22.127.325.196.72.257 1.075.124l1.217-.456a1.125 1.125 0 0 1 1.37.49l1.296 2.247a1.125 1.125 0 0 1-.26 1.431l-1.003.827c-.293.241-.438.613-.43.992a7.723 7.723 0 0 1 0 .255c-.008.378.137.75.43.991l1.004.827c.424.35.534.955.26 1.43l-1.298 2.247a1.125 1.125 0 0 1-1.369.491l-1.217-.456c-.355-.133-.75-.072-1.076.124a6.47 6.47 0 0 1-.22.128c-.331.183-.581.495-.644.869l-.213 1.281c-.09.543-.56.94-1.11.94h-2.594c-.55 0-1.019-.398-1.11-.94l-.213-1.281c-.062-.374-.312-.686-.644-.87a6.52 6.52 0 0 1-.22-.127c-.325-.196-.72-.257-1.076-.124l-1.217.456a1.125 1.125 0 0 1-1.369-.49l-1.297-2.247a1.125 1.125 0 0 1 .26-1.431l1.004-.827c.292-.24.437-.613.43-.991a6.932 6.932 0 0 1 0-.255c.007-.38-.138-.751-.43-.992l-1.004-.827a1.125 1.125 0 0 1-.26-1.43l1.297-2.247a1.125 1.125 0 0 1 1.37-.491l1.216.456c.356.133.751.072 1.076-.124.072-.044.146-.086.22-.128.332-.183.582-.495.644-.869l.214-1.28Z" />
+                    <path stroke-linecap="round" stroke-linejoin="round" d="M15 12a3 3 0 1 1-6 0 3 3 0 0 1 6 0Z" />
+                </svg>
+            </div>
         </div>
     }
 }
@@ -0,0 +1,177 @@
+use leptos::*;
+use serde::Deserialize;
+
+use crate::error::StrError;
+use crate::node::NodeStatus;
+use crate::state::State;
+use crate::tauri::{handle_invoke_args, handle_invoke_base, tauri_invoke};
+
+/// What `node_project` found, see `rnpm_core::manager::Project`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Project {
+    pub dir: String,
+    pub file: String,
+    pub spec: String,
+    pub installed: Option<String>,
+    pub available: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Offers to use or install the version the project around `State::project_dir` pins.
+#[component]
+pub fn ProjectView() -> impl IntoView {
+    let state = use_context::<State>().expect("get state failed");
+    let dismissed = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+
+    // what's installed and available changes what the pinned version resolves to
+    let _ = watch(
+        move || {
+            (
+                state.project_dir.get(),
+                state.all_nodes.with(Vec::len),
+                state.local_versions.get(),
+            )
+        },
+        move |(dir, _, _), _, _| {
+            let args = serde_json::json!({ "dir": dir });
+            spawn_local(async move {
+                match handle_invoke_args::<Option<Project>>("node_project", &args).await {
+                    Ok(project) => state.project.set(project),
+                    Err(e) => error.set(Some(StrError::from(e).to_string())),
+                }
+            });
+        },
+        true,
+    );
+
+    // a newly picked folder shows up even after dismissing the previous one
+    let _ = watch(
+        move || state.project_dir.get(),
+        move |_, _, _| {
+            error.set(None);
+            dismissed.set(false);
+        },
+        false,
+    );
+
+    let pick = create_action(move |_: &()| async move {
+        if let Err(e) = pick_project_dir(state.project_dir).await {
+            error.set(Some(e));
+        }
+    });
+
+    let use_version = create_action(move |version: &String| {
+        let version = version.clone();
+        async move {
+            let args = serde_json::json!({ "version": version });
+            if let Err(e) = tauri_invoke!("node_set_cur_version", &args).await {
+                error.set(Some(StrError::from(e).to_string()));
+            }
+        }
+    });
+
+    let install = create_action(move |version: &String| {
+        let version = version.clone();
+        let status = state
+            .all_nodes
+            .get_untracked()
+            .into_iter()
+            .find(|node| node.version == version)
+            .map(|node| node.status);
+        if let Some(status) = status {
+            status.set(NodeStatus::Queued(0));
+        }
+        async move {
+            let args = serde_json::json!({ "version": version });
+            if let Err(e) = tauri_invoke!("node_download", &args).await {
+                if let Some(status) = status {
+                    status.set(NodeStatus::Pendding);
+                }
+                error.set(Some(StrError::from(e).to_string()));
+            }
+        }
+    });
+
+    let message = move || {
+        let Some(project) = state.project.get() else {
+            let dir = state.project_dir.get()?;
+            return Some(view! {
+                <p class="text-gray-500">"no .nvmrc, .node-version or engines.node from " {file_name(&dir).to_string()}</p>
+            }.into_view());
+        };
+        let name = file_name(&project.dir);
+        let wants = format!(
+            "{name} wants {} ({})",
+            project.spec,
+            file_name(&project.file)
+        );
+
+        let view = match (&project.error, &project.installed, &project.available) {
+            (Some(e), _, _) => {
+                view! { <p class="text-red-500">{wants} ": " {e.clone()}</p> }.into_view()
+            }
+            (None, Some(installed), _) if state.cur_version.get().as_ref() == Some(installed) => {
+                view! { <p class="text-gray-500">{wants} ", using " {installed.clone()}</p> }
+                    .into_view()
+            }
+            (None, Some(installed), _) => {
+                let installed = installed.clone();
+                view! {
+                    <p>{wants} ", " {installed.clone()} " is installed"</p>
+                    <button class="text-blue-500" on:click=move |_| use_version.dispatch(installed.clone())>Use</button>
+                }
+                .into_view()
+            }
+            (None, None, Some(available)) => {
+                let available = available.clone();
+                view! {
+                    <p>{wants} ", install " {available.clone()} "?"</p>
+                    <button class="text-blue-500" on:click=move |_| install.dispatch(available.clone())>Install</button>
+                }
+                .into_view()
+            }
+            (None, None, None) => {
+                view! { <p class="text-yellow-500">{wants} ", no version matches"</p> }.into_view()
+            }
+        };
+        Some(view)
+    };
+
+    view! {
+        <Show when=move || !dismissed.get() && (state.project.with(Option::is_some) || state.project_dir.with(Option::is_some))>
+            <div class="flex items-center gap-3 rounded-md bg-blue-50 px-3 py-1 text-gray-700">
+                {message}
+                {move || error.get().map(|e| view! { <p class="truncate text-sm text-red-500" title=e.clone()>{e}</p> })}
+                <button class="ml-auto text-gray-500" on:click=move |_| pick.dispatch(())>Change</button>
+                <button class="text-gray-500" on:click=move |_| dismissed.set(true)>Dismiss</button>
+            </div>
+        </Show>
+    }
+}
+
+/// Ask the backend for a project folder to look at instead of the one the app started in.
+pub async fn pick_project_dir(project_dir: RwSignal<Option<String>>) -> Result<(), String> {
+    match handle_invoke_base::<Option<String>>("node_project_pick").await {
+        Ok(Some(dir)) => {
+            project_dir.set(Some(dir));
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(StrError::from(e).to_string()),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\'])
+        .find(|part| !part.is_empty())
+        .unwrap_or(path)
+}
+
+#[test]
+fn test_file_name() {
+    assert_eq!(file_name("/home/me/app"), "app");
+    assert_eq!(file_name("/home/me/app/"), "app");
+    assert_eq!(file_name(r"C:\work\app\.nvmrc"), ".nvmrc");
+    assert_eq!(file_name("/"), "/");
+}
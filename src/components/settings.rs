@@ -0,0 +1,220 @@
+use leptos::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::StrError;
+use crate::state::State;
+use crate::tauri::{handle_invoke_args, handle_invoke_base, tauri_invoke};
+
+/// What `shell_setup_plan` reports for one shell, see `rnpm_core::shell::ShellSetup`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ShellSetup {
+    pub shell: String,
+    pub rc_file: String,
+    pub snippet: String,
+    pub installed: bool,
+    pub legacy: bool,
+    pub legacy_rc_file: Option<String>,
+}
+
+#[component]
+pub fn SettingsView() -> impl IntoView {
+    let state = use_context::<State>().expect("get state failed");
+    let config = create_rw_signal(Value::Null);
+    let error = create_rw_signal(None::<String>);
+
+    create_resource(
+        || (),
+        move |_| async move {
+            match tauri_invoke!(Value, "config_get").await {
+                Ok(value) => config.set(value),
+                Err(e) => error.set(Some(StrError::from(e).to_string())),
+            }
+        },
+    );
+
+    let save = create_action(move |_: &()| {
+        let value = config.get_untracked();
+        async move {
+            match tauri_invoke!(Value, "config_set", &serde_json::json!({"config": value})).await {
+                Ok(value) => {
+                    config.set(value);
+                    error.set(None);
+                    state.show_settings.set(false);
+                }
+                Err(e) => error.set(Some(StrError::from(e).to_string())),
+            }
+        }
+    });
+
+    let text_field = move |key: &'static str, label: &'static str| {
+        view! {
+            <label class="flex flex-col gap-1 text-gray-500">
+                {label}
+                <input
+                    class="rounded-md border-2 border-gray-400 px-3 py-1 text-gray-700 outline-none focus:border-blue-400"
+                    prop:value=move || config.with(|c| c[key].as_str().unwrap_or_default().to_string())
+                    on:input=move |ev| {
+                        let v = event_target_value(&ev);
+                        config.update(|c| {
+                            c[key] = if v.is_empty() && (key == "proxy" || key == "no_proxy") { Value::Null } else { Value::from(v) };
+                        });
+                    } />
+            </label>
+        }
+    };
+
+    view! {
+        <div class="fixed inset-0 z-20 flex bg-black/30">
+            <div class="m-auto flex w-2/3 flex-col gap-4 rounded-xl bg-white p-6">
+                <p class="text-2xl text-blue-500">Settings</p>
+
+                <label class="flex flex-col gap-1 text-gray-500">
+                    "Mirrors (comma separated, tried in order)"
+                    <input
+                        class="rounded-md border-2 border-gray-400 px-3 py-1 text-gray-700 outline-none focus:border-blue-400"
+                        prop:value=move || config.with(|c| {
+                            c["mirrors"]
+                                .as_array()
+                                .map(|mirrors| mirrors.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", "))
+                                .unwrap_or_default()
+                        })
+                        on:change=move |ev| {
+                            let mirrors = event_target_value(&ev)
+                                .split(',')
+                                .map(|mirror| mirror.trim().to_string())
+                                .filter(|mirror| !mirror.is_empty())
+                                .collect::<Vec<_>>();
+                            config.update(|c| c["mirrors"] = Value::from(mirrors));
+                        } />
+                </label>
+                {text_field("node_dir", "Install directory")}
+
+                <label class="flex flex-col gap-1 text-gray-500">
+                    Download concurrency
+                    <input
+                        type="number"
+                        min="1"
+                        class="rounded-md border-2 border-gray-400 px-3 py-1 text-gray-700 outline-none focus:border-blue-400"
+                        prop:value=move || config.with(|c| c["download_concurrency"].as_u64().unwrap_or(1).to_string())
+                        on:input=move |ev| {
+                            if let Ok(v) = event_target_value(&ev).parse::<u64>() {
+                                config.update(|c| c["download_concurrency"] = Value::from(v));
+                            }
+                        } />
+                </label>
+
+                <label class="flex flex-row items-center gap-2 text-gray-500">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || config.with(|c| c["rosetta"].as_bool().unwrap_or_default())
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            config.update(|c| c["rosetta"] = Value::from(checked));
+                        } />
+                    "Install x64 builds under Rosetta when there is no arm64 one (macOS)"
+                </label>
+
+                {text_field("proxy", "Proxy")}
+                {text_field("no_proxy", "No proxy")}
+
+                <ShellSetupView />
+
+                {move || error.get().map(|message| view! {
+                    <p class="break-words text-red-500">{message}</p>
+                })}
+
+                <div class="flex justify-end gap-3">
+                    <button class="text-gray-500" on:click=move |_| state.show_settings.set(false)>Cancel</button>
+                    <button class="text-blue-500" on:click=move |_| save.dispatch(())>Save</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Adds or removes the `rnpm env` hook in each shell's startup file, showing the file and
+/// the lines before touching it.
+#[component]
+fn ShellSetupView() -> impl IntoView {
+    let setups = create_rw_signal(Vec::<ShellSetup>::new());
+    let shown = create_rw_signal(None::<String>);
+    let error = create_rw_signal(None::<String>);
+
+    create_resource(
+        || (),
+        move |_| async move {
+            match handle_invoke_base::<Vec<ShellSetup>>("shell_setup_plan").await {
+                Ok(value) => setups.set(value),
+                Err(e) => error.set(Some(StrError::from(e).to_string())),
+            }
+        },
+    );
+
+    let change = create_action(move |(cmd, shell): &(&'static str, String)| {
+        let cmd = *cmd;
+        let args = serde_json::json!({ "shell": shell });
+        async move {
+            match handle_invoke_args::<Vec<ShellSetup>>(cmd, &args).await {
+                Ok(value) => {
+                    setups.set(value);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(StrError::from(e).to_string())),
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-col gap-1 text-gray-500">
+            "Shell integration (switches node with the project folder on cd)"
+            <For
+                each=move || setups.get()
+                key=|setup| (setup.shell.clone(), setup.installed, setup.legacy)
+                children=move |setup| {
+                    let shell = setup.shell.clone();
+                    let snippet = setup.snippet.clone();
+                    // zsh had the old PATH line in .zshenv, which changes too
+                    let files = match &setup.legacy_rc_file {
+                        Some(legacy) => format!("{} and {legacy}", setup.rc_file),
+                        None => setup.rc_file.clone(),
+                    };
+                    let status = match (setup.installed, setup.legacy) {
+                        (true, _) => "installed",
+                        (false, true) => "old PATH line, install to replace it",
+                        (false, false) => "",
+                    };
+                    let (cmd, label) = if setup.installed {
+                        ("shell_setup_remove", "Remove")
+                    } else {
+                        ("shell_setup_install", "Install")
+                    };
+                    view! {
+                        <div class="flex items-center gap-3 text-gray-700">
+                            <p class="w-24">{setup.shell.clone()}</p>
+                            <p class="truncate text-sm text-gray-500" title=files.clone()>{files.clone()}</p>
+                            <p class="text-sm text-green-500">{status}</p>
+                            <button class="ml-auto text-gray-500" on:click={
+                                let shell = shell.clone();
+                                move |_| shown.update(|s| *s = if s.as_ref() == Some(&shell) { None } else { Some(shell.clone()) })
+                            }>Show</button>
+                            <button class="text-blue-500" on:click={
+                                let shell = shell.clone();
+                                move |_| change.dispatch((cmd, shell.clone()))
+                            }>{label}</button>
+                        </div>
+                        <Show when={
+                            let shell = shell.clone();
+                            move || shown.get().as_ref() == Some(&shell)
+                        }>
+                            <pre class="overflow-x-auto rounded-md bg-gray-100 p-2 text-xs text-gray-700">{snippet.clone()}</pre>
+                        </Show>
+                    }
+                }
+            />
+            {move || error.get().map(|message| view! {
+                <p class="break-words text-red-500">{message}</p>
+            })}
+        </div>
+    }
+}
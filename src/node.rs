@@ -1,34 +1,165 @@
 use leptos::RwSignal;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NodeStatus {
+    #[default]
     Pendding,
+    Queued(usize),
     Downloading(usize),
+    Unpacking,
+    /// Installed but incomplete or not running, see `node_verify`.
+    Broken,
     Ready,
     CurVer,
 }
 
+impl NodeStatus {
+    /// Whether a download of this version is in flight.
+    pub fn is_busy(&self) -> bool {
+        matches!(
+            self,
+            Self::Queued(_) | Self::Downloading(_) | Self::Unpacking
+        )
+    }
+}
+
 #[test]
 fn test_node_status_ord() {
-    assert!(NodeStatus::Pendding < NodeStatus::Downloading(1));
+    assert!(NodeStatus::Pendding < NodeStatus::Queued(1));
+    assert!(NodeStatus::Queued(3) < NodeStatus::Downloading(1));
     assert!(NodeStatus::Downloading(11) < NodeStatus::Downloading(22));
-    assert!(NodeStatus::Downloading(33) < NodeStatus::Ready);
+    assert!(NodeStatus::Downloading(33) < NodeStatus::Unpacking);
+    assert!(NodeStatus::Unpacking < NodeStatus::Broken);
+    assert!(NodeStatus::Broken < NodeStatus::Ready);
     assert!(NodeStatus::Ready < NodeStatus::CurVer);
 }
 
-impl Default for NodeStatus {
-    fn default() -> Self {
-        Self::Pendding
-    }
-}
-
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Node {
     pub version: String,
     pub lts: Option<String>,
+    /// Whether there is a build for this platform, installed versions always are.
+    #[serde(default = "available")]
+    pub available: bool,
     #[serde(skip)]
     pub hidden: RwSignal<bool>,
     #[serde(skip)]
     pub status: RwSignal<NodeStatus>,
+    /// Installed but missing from the remote list, or the remote list isn't there yet.
+    #[serde(skip)]
+    pub local_only: bool,
+}
+
+impl Node {
+    fn local(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            lts: None,
+            available: true,
+            hidden: RwSignal::default(),
+            status: RwSignal::default(),
+            local_only: true,
+        }
+    }
+}
+
+fn available() -> bool {
+    true
+}
+
+/// Combine the remote list with the installed versions it doesn't know about.
+///
+/// Nodes already in `current` keep their signals, the views rendered for them stay in sync.
+pub fn merge_nodes(current: &[Node], remote: Vec<Node>, local_versions: &[String]) -> Vec<Node> {
+    let existing = |version: &str| current.iter().find(|node| node.version == version);
+
+    let mut nodes = remote
+        .into_iter()
+        .map(|node| match existing(&node.version) {
+            Some(cur) => Node {
+                lts: node.lts,
+                available: node.available,
+                local_only: false,
+                ..cur.clone()
+            },
+            None => node,
+        })
+        .collect::<Vec<_>>();
+
+    let mut local_only = local_versions
+        .iter()
+        .filter(|version| !nodes.iter().any(|node| &node.version == *version))
+        .map(|version| match existing(version) {
+            Some(cur) => Node {
+                local_only: true,
+                ..cur.clone()
+            },
+            None => Node::local(version),
+        })
+        .collect::<Vec<_>>();
+    local_only.sort_by_key(|node| std::cmp::Reverse(version_key(&node.version)));
+
+    nodes.extend(local_only);
+    nodes
+}
+
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn test_merge_nodes() {
+    use leptos::{SignalGetUntracked, SignalSet};
+
+    let runtime = leptos::create_runtime();
+    let versions = |nodes: &[Node]| {
+        nodes
+            .iter()
+            .map(|node| (node.version.clone(), node.local_only))
+            .collect::<Vec<_>>()
+    };
+    let local = [
+        "v8.17.0".to_string(),
+        "v20.11.1".to_string(),
+        "v18.19.1".to_string(),
+    ];
+
+    // offline, only what's installed
+    let nodes = merge_nodes(&[], vec![], &local);
+    assert_eq!(
+        versions(&nodes),
+        [
+            ("v20.11.1".to_string(), true),
+            ("v18.19.1".to_string(), true),
+            ("v8.17.0".to_string(), true)
+        ]
+    );
+
+    // the remote list arrives and keeps the signals already handed out
+    nodes[0].status.set(NodeStatus::CurVer);
+    let remote = ["v21.6.2", "v20.11.1", "v18.19.1"]
+        .iter()
+        .map(|version| Node {
+            local_only: false,
+            ..Node::local(version)
+        })
+        .collect();
+    let merged = merge_nodes(&nodes, remote, &local);
+    assert_eq!(
+        versions(&merged),
+        [
+            ("v21.6.2".to_string(), false),
+            ("v20.11.1".to_string(), false),
+            ("v18.19.1".to_string(), false),
+            ("v8.17.0".to_string(), true)
+        ]
+    );
+    assert_eq!(merged[1].status.get_untracked(), NodeStatus::CurVer);
+
+    runtime.dispose();
 }
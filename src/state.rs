@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use leptos::RwSignal;
 
+use crate::components::project::Project;
 use crate::node::Node;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -19,6 +22,24 @@ pub struct State {
     pub all_nodes: RwSignal<Vec<Node>>,
     pub local_versions: RwSignal<Vec<String>>,
     pub cur_version: RwSignal<Option<String>>,
+    /// Installed versions that failed `node_verify`, with what's wrong with them.
+    pub broken: RwSignal<HashMap<String, Vec<String>>>,
     pub filter_version: RwSignal<String>,
+    /// Read the filter as a range or alias like `^18.17` or `lts/*` instead of a substring.
+    pub filter_range: RwSignal<bool>,
+    /// What `node_resolve` matched for the range, `None` while there is none to apply.
+    pub filter_matches: RwSignal<Option<Vec<String>>>,
+    pub filter_error: RwSignal<Option<String>>,
     pub display_mod: RwSignal<DisplayMode>,
+    pub list_retry: RwSignal<Option<(u64, u64)>>,
+    pub list_fetched_at: RwSignal<Option<u64>>,
+    pub list_error: RwSignal<Option<String>>,
+    /// Why the list couldn't be written to the cache, it's fetched again next start.
+    pub list_cache_error: RwSignal<Option<String>>,
+    /// `(phase, message)` of the startup phases that failed.
+    pub init_errors: RwSignal<Vec<(String, String)>>,
+    pub show_settings: RwSignal<bool>,
+    /// The folder picked to look for a pinned version in, the one the app started in otherwise.
+    pub project_dir: RwSignal<Option<String>>,
+    pub project: RwSignal<Option<Project>>,
 }